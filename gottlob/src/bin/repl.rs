@@ -1,52 +1,91 @@
+use gottlob::logic::classical::parser_reverse_polish::ClassicalRpParser;
+use gottlob::logic::latex::Latex;
+use gottlob::logic::modal::ModalLogic;
 use gottlob::logic::*;
+use std::fmt::Display;
+use std::io::Write;
 use structopt::*;
 
 #[derive(Debug, StructOpt)]
-enum Opt {
+struct Opt {
+  /// Print formulas and tableaux as LaTeX, for the `amssymb` and `forest` packages.
+  #[structopt(long)]
+  latex: bool,
+  #[structopt(subcommand)]
+  logic: LogicOpt,
+}
+
+#[derive(Debug, StructOpt)]
+enum LogicOpt {
   Classical,
   ClassicalRp,
+  Modal {
+    /// One of K, D, T, B, S4 or S5, or the tense logics Kt, Kt4, Kt4.3 or Q.
+    #[structopt(default_value = "K")]
+    system: String,
+  },
+}
+
+/// Prints a theorem, whether its claim holds, or `None` if we couldn't tell, and the tableau for it.  A tableau that was
+/// given up on is printed as far as it got.
+fn report<T: Display + Latex, U: Display + Latex>(thm: &T, holds: Option<bool>, tableau: &U, latex: bool) {
+  let holds = holds.map_or_else(|| "undecided".to_owned(), |h| h.to_string());
+  if latex {
+    println!("${}$ % {}", thm.latex(), holds);
+    println!("{}", tableau.latex());
+  } else {
+    println!("{}", thm);
+    println!("{}", holds);
+    print!("{}", tableau);
+  }
 }
 
 fn main() {
   let opt = Opt::from_args();
-
-  // TODO: make DRY (trait time, I think!)
-  // TODO: clean quit
-  println!("Welcome to Gottlob REPL!  Enter expressions.");
-  match opt {
-    Opt::Classical => loop {
-      print!("> ");
-      let mut l = String::new();
-      std::io::stdin()
-        .read_line(&mut l)
-        .expect("Failed to read line");
-      let expr = match classical::ExpressionParser::parse_expression(&l) {
-        Ok(expr) => expr,
-        Err(e) => {
-          println!("Unable to parse {:?}", e);
-          continue;
+  let modal_logic = match &opt.logic {
+    LogicOpt::Modal { system } => {
+      match ModalLogic::SYSTEMS.iter().chain(&ModalLogic::TENSE_SYSTEMS).find(|l| l.name() == system) {
+        Some(logic) => Some(*logic),
+        None => {
+          eprintln!("Unknown modal system {:?}", system);
+          std::process::exit(1);
         }
-      };
+      }
+    }
+    _ => None,
+  };
 
-      println!("{}", expr);
-      println!("{}", expr.is_tautology());
-    },
-    Opt::ClassicalRp => loop {
-      let mut l = String::new();
-      print!("> ");
-      std::io::stdin()
-        .read_line(&mut l)
-        .expect("Failed to read line");
-      let expr = match classical::reverse_polish::RpParser::parse_expression(&l) {
-        Ok(expr) => expr,
-        Err(e) => {
-          println!("Unable to parse {:?}", e);
-          continue;
-        }
-      };
+  println!("Welcome to Gottlob REPL!  Enter expressions or theorems, and an empty line or end of input to quit.");
+  loop {
+    print!("> ");
+    std::io::stdout().flush().expect("Failed to flush stdout");
+    let mut l = String::new();
+    let read = std::io::stdin().read_line(&mut l).expect("Failed to read line");
+    let l = l.trim();
+    if read == 0 || l.is_empty() {
+      return;
+    }
 
-      println!("{}", expr);
-      println!("{}", expr.is_tautology());
-    },
+    let result = match (&opt.logic, modal_logic) {
+      (LogicOpt::Modal { .. }, Some(logic)) => modal::parse(l).map(|thm| {
+        let tableau = modal::tableau::Tableau::for_theorem(&thm, logic.frame_conditions());
+        report(&thm, modal::check(&thm, &tableau), &tableau, opt.latex);
+      }),
+      (LogicOpt::ClassicalRp, _) => ClassicalRpParser::parse_expression(l).map(|e| {
+        let thm = classical::Theorem::Proves {
+          assumptions: Vec::new(),
+          conclusion: e,
+        };
+        let tableau = classical::tableau::Tableau::for_theorem(&thm);
+        report(&thm, Some(classical::check(&thm)), &tableau, opt.latex);
+      }),
+      _ => classical::parse(l).map(|thm| {
+        let tableau = classical::tableau::Tableau::for_theorem(&thm);
+        report(&thm, Some(classical::check(&thm)), &tableau, opt.latex);
+      }),
+    };
+    if let Err(e) = result {
+      println!("{}", e);
+    }
   }
 }
@@ -0,0 +1,447 @@
+//! Which truth functions a set of connectives can express.
+//!
+//! By Post's theorem a set of connectives is functionally complete exactly when, for each of five classes of truth
+//! functions closed under composition, some connective in the set falls outside it.  When a connective can be defined
+//! from a set, `define` finds a shortest defining formula by building every truth function the set expresses, smallest
+//! formulas first.
+
+use super::{Expression, Variable};
+use crate::logic::formula::{Formula, Operator, Shape};
+use std::collections::{HashMap, HashSet};
+
+/// Targets of `define` can have at most this many places, since the search goes through truth functions of that many
+/// arguments and there are `2^2^n` of them.
+pub const MAX_DEFINITION_ARITY: usize = 3;
+
+/// The names `define` gives the arguments of the connective it defines.
+const ARGUMENT_NAMES: [&str; MAX_DEFINITION_ARITY] = ["p", "q", "r"];
+
+/// The five classes of Post's theorem.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub enum PostClass {
+  /// False when every argument is false.
+  FalsePreserving,
+  /// True when every argument is true.
+  TruePreserving,
+  /// Negating every argument negates the value.
+  SelfDual,
+  /// Making an argument true never makes the value false.
+  Monotone,
+  /// An exclusive or of some arguments and possibly `⊤`.
+  Affine,
+}
+
+impl PostClass {
+  pub const ALL: [Self; 5] = [
+    Self::FalsePreserving,
+    Self::TruePreserving,
+    Self::SelfDual,
+    Self::Monotone,
+    Self::Affine,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::FalsePreserving => "falsity-preserving",
+      Self::TruePreserving => "truth-preserving",
+      Self::SelfDual => "self-dual",
+      Self::Monotone => "monotone",
+      Self::Affine => "affine",
+    }
+  }
+}
+
+impl std::fmt::Display for PostClass {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+/// A truth function with a name.  Its table lists its value for each row, numbering rows by reading the arguments as
+/// the bits of a binary number with the first argument most significant and true as 1.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Connective {
+  name: String,
+  arity: usize,
+  table: Vec<bool>,
+  /// The operator or constant this is, for the connectives the language has.
+  builtin: Option<Shape<()>>,
+}
+
+impl Connective {
+  /// A connective given by its truth table, which must have `2^arity` rows.
+  pub fn new(name: &str, arity: usize, table: Vec<bool>) -> Self {
+    assert_eq!(table.len(), 1 << arity, "a connective of {} places needs {} rows", arity, 1 << arity);
+    Self {
+      name: name.to_owned(),
+      arity,
+      table,
+      builtin: None,
+    }
+  }
+
+  /// The connective `e` expresses as a function of `arguments`, which must include every letter in it.
+  pub fn from_expression(name: &str, e: &Expression, arguments: &[Variable]) -> Self {
+    assert!(e.variables().iter().all(|v| arguments.contains(v)), "{} has letters besides the arguments", e);
+    let n = arguments.len();
+    let table = (0..1 << n)
+      .map(|row| {
+        let trues = (0..n)
+          .filter(|j| row & (1 << (n - 1 - j)) != 0)
+          .map(|j| arguments[j])
+          .collect::<HashSet<_>>();
+        e.eval(&trues)
+      })
+      .collect();
+    Self::new(name, n, table)
+  }
+
+  /// One of the classical operators.
+  pub fn from_operator(op: Operator) -> Self {
+    let arguments = ARGUMENT_NAMES[..if op.is_unary() { 1 } else { 2 }]
+      .iter()
+      .map(|name| Variable::new(name))
+      .collect::<Vec<_>>();
+    let letters = arguments.iter().map(|v| Expression::Variable(*v)).collect::<Vec<_>>();
+    let shape = match letters.as_slice() {
+      [a] => Shape::Unary(op, a.clone()),
+      [a, b] => Shape::Binary(op, a.clone(), b.clone()),
+      _ => unreachable!(),
+    };
+    let e = Expression::try_from_shape(shape).unwrap_or_else(|op| panic!("{:?} is not a classical operator", op));
+    Self {
+      builtin: Some(if op.is_unary() { Shape::Unary(op, ()) } else { Shape::Binary(op, (), ()) }),
+      ..Self::from_expression(&op.symbol(), &e, &arguments)
+    }
+  }
+
+  /// `⊤` or `⊥`, as a connective of no places.
+  pub fn constant(value: bool) -> Self {
+    Self {
+      builtin: Some(Shape::Constant(value)),
+      ..Self::new(if value { "⊤" } else { "⊥" }, 0, vec![value])
+    }
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn arity(&self) -> usize {
+    self.arity
+  }
+
+  pub fn value(&self, arguments: &[bool]) -> bool {
+    assert_eq!(arguments.len(), self.arity);
+    self.table[arguments.iter().fold(0, |row, t| row << 1 | *t as usize)]
+  }
+
+  pub fn is_in(&self, class: PostClass) -> bool {
+    let t = &self.table;
+    let last = t.len() - 1;
+    match class {
+      PostClass::FalsePreserving => !t[0],
+      PostClass::TruePreserving => t[last],
+      PostClass::SelfDual => (0..t.len()).all(|row| t[row] != t[!row & last]),
+      PostClass::Monotone => (0..t.len()).all(|row| (0..self.arity).all(|j| !t[row] || t[row | 1 << j])),
+      PostClass::Affine => {
+        // The constant term is the value at row 0, and each argument's coefficient is what flipping it alone does.
+        let coefficients = (0..self.arity).filter(|j| t[1 << j] != t[0]).fold(0, |c, j| c | 1 << j);
+        (0..t.len()).all(|row| t[row] == (t[0] ^ ((row & coefficients).count_ones() % 2 == 1)))
+      }
+    }
+  }
+
+  /// The Post classes this connective belongs to.
+  pub fn post_classes(&self) -> Vec<PostClass> {
+    PostClass::ALL.iter().cloned().filter(|c| self.is_in(*c)).collect()
+  }
+}
+
+impl std::fmt::Display for Connective {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name)
+  }
+}
+
+/// The Post classes containing every connective in `set`.  Everything `set` defines stays inside them, so `set` is
+/// functionally complete exactly when there are none.
+pub fn confining_classes(set: &[Connective]) -> Vec<PostClass> {
+  PostClass::ALL
+    .iter()
+    .cloned()
+    .filter(|class| set.iter().all(|c| c.is_in(*class)))
+    .collect()
+}
+
+pub fn is_functionally_complete(set: &[Connective]) -> bool {
+  confining_classes(set).is_empty()
+}
+
+/// A formula built from arguments and connectives, which are numbered by their place in a set.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub enum Term {
+  Argument(usize),
+  Apply(usize, Vec<Term>),
+}
+
+impl Term {
+  pub fn size(&self) -> usize {
+    match self {
+      Self::Argument(_) => 1,
+      Self::Apply(_, arguments) => 1 + arguments.iter().map(|a| a.size()).sum::<usize>(),
+    }
+  }
+}
+
+/// A definition of one connective in terms of a set of others.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Definition {
+  pub target: Connective,
+  pub set: Vec<Connective>,
+  pub term: Term,
+}
+
+impl Definition {
+  /// The arguments of the defined connective, as the letters `p`, `q` and `r`.
+  pub fn arguments(&self) -> Vec<Variable> {
+    ARGUMENT_NAMES[..self.target.arity].iter().map(|name| Variable::new(name)).collect()
+  }
+
+  /// The definition as a sentence, if every connective it uses is one of the language's operators or constants.
+  pub fn to_expression(&self) -> Option<Expression> {
+    build(&self.term, &self.set, &self.arguments())
+  }
+}
+
+fn build(term: &Term, set: &[Connective], arguments: &[Variable]) -> Option<Expression> {
+  match term {
+    Term::Argument(i) => Some(Expression::Variable(arguments[*i])),
+    Term::Apply(c, operands) => {
+      let mut operands = operands
+        .iter()
+        .map(|t| build(t, set, arguments))
+        .collect::<Option<Vec<_>>>()?
+        .into_iter();
+      let shape = match set[*c].builtin.as_ref()? {
+        Shape::Constant(value) => Shape::Constant(*value),
+        Shape::Unary(op, ()) => Shape::Unary(*op, operands.next()?),
+        Shape::Binary(op, (), ()) => Shape::Binary(*op, operands.next()?, operands.next()?),
+        Shape::Variable(_) => return None,
+      };
+      Some(Expression::from_shape(shape))
+    }
+  }
+}
+
+/// Writes the definition as `p ∨ q ≡ ...`, or `maj(p, q, r) ≡ ...` for a connective the language doesn't have.
+/// Such connectives are written as functions, with parentheses around any operator inside them.
+impl std::fmt::Display for Definition {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn write_term(term: &Term, d: &Definition, outermost: bool) -> String {
+      match term {
+        Term::Argument(i) => ARGUMENT_NAMES[*i].to_owned(),
+        Term::Apply(c, operands) => {
+          let connective = &d.set[*c];
+          let operands = operands.iter().map(|t| write_term(t, d, false)).collect::<Vec<_>>();
+          match (&connective.builtin, operands.as_slice()) {
+            (Some(Shape::Unary(_, ())), [a]) => format!("{}{}", connective.name, a),
+            (Some(Shape::Binary(_, (), ())), [a, b]) if outermost => format!("{} {} {}", a, connective.name, b),
+            (Some(Shape::Binary(_, (), ())), [a, b]) => format!("({} {} {})", a, connective.name, b),
+            (_, []) => connective.name.clone(),
+            (_, operands) => format!("{}({})", connective.name, operands.join(", ")),
+          }
+        }
+      }
+    }
+    let target = Term::Apply(0, (0..self.target.arity).map(Term::Argument).collect());
+    let defined = match build(&target, std::slice::from_ref(&self.target), &self.arguments()) {
+      Some(e) => e.to_string(),
+      None => format!("{}({})", self.target.name, ARGUMENT_NAMES[..self.target.arity].join(", ")),
+    };
+    let body = match self.to_expression() {
+      Some(e) => e.to_string(),
+      None => write_term(&self.term, self, true),
+    };
+    write!(f, "{} ≡ {}", defined, body)
+  }
+}
+
+/// A shortest formula in the connectives of `set` with the same truth table as `target`, if there is one.  Formulas
+/// are measured by how many connectives and arguments they have.  `target` can have at most `MAX_DEFINITION_ARITY`
+/// places.
+pub fn define(target: &Connective, set: &[Connective]) -> Option<Definition> {
+  let n = target.arity;
+  assert!(n <= MAX_DEFINITION_ARITY, "can't search for definitions of connectives of {} places", n);
+  let rows = 1usize << n;
+  // A truth function of the `n` arguments, as the bits of its table.
+  let goal = (0..rows).filter(|row| target.table[*row]).fold(0u64, |t, row| t | 1 << row);
+  let argument = |i: usize| (0..rows).filter(|row| row & (1 << (n - 1 - i)) != 0).fold(0u64, |t, row| t | 1 << row);
+  let apply = |c: &Connective, operands: &[u64]| {
+    (0..rows)
+      .filter(|row| c.value(&operands.iter().map(|o| o & (1 << row) != 0).collect::<Vec<_>>()))
+      .fold(0u64, |t, row| t | 1 << row)
+  };
+
+  // First find out whether the target is definable at all, by closing the arguments under the set.
+  let mut reachable = (0..n).map(argument).collect::<HashSet<_>>();
+  loop {
+    let known = reachable.iter().cloned().collect::<Vec<_>>();
+    let before = reachable.len();
+    for c in set {
+      for operands in tuples(&known, c.arity) {
+        reachable.insert(apply(c, &operands));
+      }
+    }
+    if reachable.len() == before {
+      break;
+    }
+  }
+  if !reachable.contains(&goal) {
+    return None;
+  }
+
+  // Then build formulas size by size until one has the target's table.
+  let mut found: HashMap<u64, Term> = HashMap::new();
+  let mut by_size: Vec<Vec<u64>> = vec![Vec::new(), Vec::new()];
+  let atoms = (0..n).map(|i| (argument(i), Term::Argument(i)));
+  let constants = set
+    .iter()
+    .enumerate()
+    .filter(|(_, c)| c.arity == 0)
+    .map(|(index, c)| (apply(c, &[]), Term::Apply(index, Vec::new())));
+  for (t, term) in atoms.chain(constants) {
+    if let std::collections::hash_map::Entry::Vacant(entry) = found.entry(t) {
+      entry.insert(term);
+      by_size[1].push(t);
+    }
+  }
+  let mut size = 1;
+  while !found.contains_key(&goal) {
+    size += 1;
+    let mut new = Vec::new();
+    for (index, c) in set.iter().enumerate() {
+      if c.arity == 0 {
+        continue;
+      }
+      for sizes in compositions(size - 1, c.arity) {
+        let choices = sizes.iter().map(|s| by_size[*s].clone()).collect::<Vec<_>>();
+        for operands in products(&choices) {
+          let t = apply(c, &operands);
+          if found.contains_key(&t) {
+            continue;
+          }
+          let term = Term::Apply(index, operands.iter().map(|o| found[o].clone()).collect());
+          found.insert(t, term);
+          new.push(t);
+        }
+      }
+    }
+    by_size.push(new);
+  }
+  Some(Definition {
+    target: target.clone(),
+    set: set.to_vec(),
+    term: found.remove(&goal).unwrap(),
+  })
+}
+
+/// Every sequence of `k` items from `items`.
+fn tuples(items: &[u64], k: usize) -> Vec<Vec<u64>> {
+  products(&vec![items.to_vec(); k])
+}
+
+/// Every sequence taking one item from each of `choices`.
+fn products(choices: &[Vec<u64>]) -> Vec<Vec<u64>> {
+  choices.iter().fold(vec![Vec::new()], |sequences, choice| {
+    sequences
+      .iter()
+      .flat_map(|s| choice.iter().map(move |c| [s.as_slice(), &[*c]].concat()))
+      .collect()
+  })
+}
+
+/// Every way of writing `total` as a sum of `parts` positive numbers, in order.
+fn compositions(total: usize, parts: usize) -> Vec<Vec<usize>> {
+  if parts == 0 {
+    return if total == 0 { vec![Vec::new()] } else { Vec::new() };
+  }
+  (1..=total)
+    .flat_map(|first| {
+      compositions(total - first, parts - 1).into_iter().map(move |mut rest| {
+        rest.insert(0, first);
+        rest
+      })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+  use crate::logic::classical::semantics::are_equivalent;
+
+  fn connectives(ops: &[Operator]) -> Vec<Connective> {
+    ops.iter().map(|op| Connective::from_operator(*op)).collect()
+  }
+
+  #[test]
+  fn test_post_classes() {
+    use PostClass::*;
+    let classes = |op| Connective::from_operator(op).post_classes();
+    assert_eq!(classes(Operator::Not), vec![SelfDual, Affine]);
+    assert_eq!(classes(Operator::And), vec![FalsePreserving, TruePreserving, Monotone]);
+    assert_eq!(classes(Operator::Conditional), vec![TruePreserving]);
+    assert_eq!(classes(Operator::Xor), vec![FalsePreserving, Affine]);
+    assert_eq!(classes(Operator::Nand), vec![]);
+    assert_eq!(Connective::constant(false).post_classes(), vec![FalsePreserving, Monotone, Affine]);
+    let majority = Connective::new("maj", 3, vec![false, false, false, true, false, true, true, true]);
+    assert_eq!(majority.post_classes(), vec![FalsePreserving, TruePreserving, SelfDual, Monotone]);
+  }
+
+  #[test]
+  fn test_completeness() {
+    use Operator::*;
+    assert!(is_functionally_complete(&connectives(&[Nand])));
+    assert!(is_functionally_complete(&connectives(&[Nor])));
+    assert!(is_functionally_complete(&connectives(&[Not, And])));
+    assert!(is_functionally_complete(&[Connective::from_operator(Conditional), Connective::constant(false)]));
+    assert_eq!(confining_classes(&connectives(&[And, Or])), vec![
+      PostClass::FalsePreserving,
+      PostClass::TruePreserving,
+      PostClass::Monotone
+    ]);
+    assert_eq!(confining_classes(&connectives(&[Not, Biconditional])), vec![PostClass::Affine]);
+    assert!(!is_functionally_complete(&connectives(&[Conditional, Or])));
+  }
+
+  #[test]
+  fn test_definitions() {
+    use Operator::*;
+    let d = define(&Connective::from_operator(Not), &connectives(&[Nand])).unwrap();
+    assert_eq!(d.to_string(), "¬p ≡ p ↑ p");
+
+    let d = define(&Connective::from_operator(Or), &connectives(&[Not, Conditional])).unwrap();
+    assert_eq!(d.to_string(), "p ∨ q ≡ ¬p → q");
+
+    let target = Connective::from_operator(Biconditional);
+    let d = define(&target, &connectives(&[Nand])).unwrap();
+    let e = d.to_expression().unwrap();
+    assert_eq!(e.operators().into_iter().collect::<Vec<_>>(), vec![Nand]);
+    let p_iff_q = ClassicalParser::parse_expression("p <-> q").unwrap();
+    assert!(are_equivalent(&e, &p_iff_q).holds, "{}", e);
+
+    assert_eq!(define(&Connective::from_operator(Not), &connectives(&[And, Or, Conditional])), None);
+    assert_eq!(define(&Connective::from_operator(Conditional), &connectives(&[Not, Biconditional])), None);
+
+    // Connectives the language doesn't have are written as functions.
+    let majority = Connective::new("maj", 3, vec![false, false, false, true, false, true, true, true]);
+    let d = define(&Connective::from_operator(And), &[majority, Connective::constant(false)]).unwrap();
+    assert_eq!(d.to_expression(), None);
+    assert_eq!(d.to_string(), "p ∧ q ≡ maj(p, q, ⊥)");
+    let three = ClassicalParser::parse_expression("p ^ q v q ^ r v p ^ r").unwrap();
+    let arguments = ["p", "q", "r"].iter().map(|v| Variable::new(v)).collect::<Vec<_>>();
+    assert_eq!(Connective::from_expression("maj", &three, &arguments), d.set[0]);
+  }
+}
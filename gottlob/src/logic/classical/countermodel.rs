@@ -0,0 +1,44 @@
+use super::{Expression, Variable};
+use std::collections::{BTreeMap, HashSet};
+
+/// An interpretation that makes the assumptions of an inference true and its conclusion false.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct Countermodel {
+  pub valuation: BTreeMap<Variable, bool>,
+}
+
+impl Countermodel {
+  /// Reads an interpretation off a completed open branch: the sentence letters that occur on their own are true, and
+  /// every other letter in `variables` is false.
+  pub fn from_branch<'a, I: IntoIterator<Item = &'a Expression>>(branch: I, variables: HashSet<Variable>) -> Self {
+    let mut valuation = variables.into_iter().map(|v| (v, false)).collect::<BTreeMap<_, _>>();
+    for e in branch {
+      if let Expression::Variable(v) = e {
+        valuation.insert(*v, true);
+      }
+    }
+    Self { valuation }
+  }
+
+  pub fn trues(&self) -> HashSet<Variable> {
+    self.valuation.iter().filter(|(_, t)| **t).map(|(v, _)| *v).collect()
+  }
+
+  /// Checks that this really is a countermodel by evaluating the inference in it.
+  pub fn is_countermodel(&self, assumptions: &[Expression], conclusion: &Expression) -> bool {
+    let trues = self.trues();
+    assumptions.iter().all(|a| a.eval(&trues)) && !conclusion.eval(&trues)
+  }
+}
+
+impl std::fmt::Display for Countermodel {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let valuation = self
+      .valuation
+      .iter()
+      .map(|(v, t)| format!("v({}) = {}", v, if *t { 1 } else { 0 }))
+      .collect::<Vec<_>>()
+      .join(", ");
+    write!(f, "{}", valuation)
+  }
+}
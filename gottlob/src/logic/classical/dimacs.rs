@@ -0,0 +1,244 @@
+//! Reading and writing the DIMACS `p cnf` format used by SAT solvers and benchmark sets.
+//!
+//! DIMACS only numbers its variables, so we write the name of each sentence letter in a comment, `c gottlob var 1 p`,
+//! and read such comments back.  Other comments are ignored, as is anything after a line starting with `%`, which some
+//! benchmark collections add.
+
+use super::sat::{Cnf, Literal};
+use super::{Expression, Variable};
+
+/// What is wrong with a DIMACS file, and on which line (counting from 1).
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct DimacsError {
+  pub line: usize,
+  pub message: String,
+}
+
+impl std::fmt::Display for DimacsError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "line {}: {}", self.line, self.message)
+  }
+}
+
+impl std::error::Error for DimacsError {}
+
+const NAME_COMMENT: &str = "c gottlob var";
+
+impl Cnf {
+  /// The clauses of a sentence in CNF as they stand, or the Tseitin encoding of any other sentence.
+  pub fn from_expression(e: &Expression) -> Self {
+    if !e.is_cnf() {
+      return Self::tseitin(std::slice::from_ref(e));
+    }
+    let mut letters = e.variables().into_iter().collect::<Vec<_>>();
+    letters.sort();
+    let mut cnf = Self::new();
+    for v in &letters {
+      cnf.add_variable(Some(*v));
+    }
+    let var = |v: &Variable| letters.binary_search(v).unwrap();
+    let mut clauses = Vec::new();
+    collect(e, true, &mut clauses);
+    for clause in clauses {
+      let mut literals = Vec::new();
+      collect(clause, false, &mut literals);
+      if literals.contains(&&Expression::Top) {
+        continue;
+      }
+      let clause = literals
+        .into_iter()
+        .filter(|l| **l != Expression::Bottom)
+        .map(|l| match l {
+          Expression::Variable(v) => Literal::new(var(v), true),
+          Expression::Negated(inner) => match inner.as_ref() {
+            Expression::Variable(v) => Literal::new(var(v), false),
+            _ => unreachable!("a CNF only negates letters"),
+          },
+          _ => unreachable!("a CNF clause is made of literals"),
+        })
+        .collect();
+      cnf.add_clause(clause);
+    }
+    cnf
+  }
+
+  pub fn to_dimacs(&self) -> String {
+    let mut lines = Vec::new();
+    for (i, name) in self.names.iter().enumerate() {
+      if let Some(v) = name {
+        lines.push(format!("{} {} {}", NAME_COMMENT, i + 1, v));
+      }
+    }
+    lines.push(format!("p cnf {} {}", self.num_variables(), self.clauses.len()));
+    for clause in &self.clauses {
+      let mut numbers = clause.iter().map(|l| dimacs_number(*l).to_string()).collect::<Vec<_>>();
+      numbers.push("0".to_owned());
+      lines.push(numbers.join(" "));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+  }
+
+  pub fn from_dimacs(s: &str) -> Result<Self, DimacsError> {
+    let mut cnf: Option<Cnf> = None;
+    let mut names = Vec::new();
+    let mut declared_clauses = 0;
+    let mut clause = Vec::new();
+    let mut last_line = 0;
+    for (i, line) in s.lines().enumerate() {
+      let error = |message: String| DimacsError { line: i + 1, message };
+      last_line = i + 1;
+      let line = line.trim();
+      if line.starts_with('%') {
+        break;
+      }
+      if let Some(rest) = line.strip_prefix(NAME_COMMENT) {
+        let mut parts = rest.split_whitespace();
+        let number = parts.next().and_then(|n| n.parse::<usize>().ok()).filter(|n| *n > 0);
+        match (number, parts.next(), parts.next()) {
+          (Some(n), Some(name), None) => names.push((n, Variable::new(name), i + 1)),
+          _ => return Err(error(format!("expected '{} <number> <name>'", NAME_COMMENT))),
+        }
+        continue;
+      }
+      if line.is_empty() || line.starts_with('c') {
+        continue;
+      }
+      if line.starts_with('p') {
+        if cnf.is_some() {
+          return Err(error("a second problem line".to_owned()));
+        }
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        let counts = match parts.as_slice() {
+          ["p", "cnf", vars, clauses] => vars.parse::<usize>().ok().zip(clauses.parse::<usize>().ok()),
+          _ => None,
+        };
+        let (vars, clauses) = counts.ok_or_else(|| error("expected 'p cnf <variables> <clauses>'".to_owned()))?;
+        cnf = Some(Cnf {
+          names: vec![None; vars],
+          clauses: Vec::new(),
+        });
+        declared_clauses = clauses;
+        continue;
+      }
+      let cnf = cnf
+        .as_mut()
+        .ok_or_else(|| error("clauses before the 'p cnf' line".to_owned()))?;
+      for token in line.split_whitespace() {
+        let n = token
+          .parse::<i64>()
+          .map_err(|_| error(format!("'{}' isn't a literal", token)))?;
+        if n == 0 {
+          cnf.clauses.push(std::mem::take(&mut clause));
+          continue;
+        }
+        let var = n.unsigned_abs() as usize;
+        if var > cnf.num_variables() {
+          return Err(error(format!(
+            "variable {} is more than the {} declared",
+            var,
+            cnf.num_variables()
+          )));
+        }
+        clause.push(Literal::new(var - 1, n > 0));
+      }
+    }
+    let mut cnf = cnf.ok_or(DimacsError {
+      line: last_line,
+      message: "no 'p cnf' line".to_owned(),
+    })?;
+    if !clause.is_empty() {
+      cnf.clauses.push(clause);
+    }
+    if cnf.clauses.len() != declared_clauses {
+      return Err(DimacsError {
+        line: last_line,
+        message: format!("{} clauses declared but {} found", declared_clauses, cnf.clauses.len()),
+      });
+    }
+    for (n, name, line) in names {
+      if n > cnf.num_variables() {
+        return Err(DimacsError {
+          line,
+          message: format!("variable {} is more than the {} declared", n, cnf.num_variables()),
+        });
+      }
+      cnf.names[n - 1] = Some(name);
+    }
+    Ok(cnf)
+  }
+}
+
+fn dimacs_number(l: Literal) -> i64 {
+  let n = l.var() as i64 + 1;
+  if l.is_positive() {
+    n
+  } else {
+    -n
+  }
+}
+
+/// The operands of a run of conjunctions if `conjunction`, and of disjunctions otherwise.
+fn collect<'a>(e: &'a Expression, conjunction: bool, out: &mut Vec<&'a Expression>) {
+  match (e, conjunction) {
+    (Expression::And(a, b), true) | (Expression::Or(a, b), false) => {
+      collect(a, conjunction, out);
+      collect(b, conjunction, out);
+    }
+    _ => out.push(e),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+  use crate::logic::classical::semantics::are_equivalent;
+
+  fn parse(s: &str) -> Expression {
+    ClassicalParser::parse_expression(s).unwrap()
+  }
+
+  #[test]
+  fn test_export() {
+    let cnf = Cnf::from_expression(&parse("(rain v ~q) ^ q ^ (F v ~rain v q)"));
+    assert_eq!(
+      cnf.to_dimacs(),
+      "c gottlob var 1 q\nc gottlob var 2 rain\np cnf 2 3\n2 -1 0\n1 0\n-2 1 0\n"
+    );
+    let tseitin = Cnf::from_expression(&parse("p -> q"));
+    assert!(tseitin.num_variables() > 2);
+    assert!(tseitin.to_dimacs().starts_with("c gottlob var 1 p\nc gottlob var 2 q\np cnf 3 "));
+  }
+
+  #[test]
+  fn test_import() {
+    let cnf = Cnf::from_dimacs("c a benchmark\np cnf 3 2\n1 -3 0\n2 3\n -1 0\n%\n0\n").unwrap();
+    assert_eq!(cnf.num_variables(), 3);
+    assert_eq!(cnf.clauses.len(), 2);
+    assert_eq!(cnf.to_expression("x").to_string(), "(x1 ∨ ¬x3) ∧ (x2 ∨ x3 ∨ ¬x1)");
+    assert!(cnf.solve().is_some());
+
+    let err = |s: &str| Cnf::from_dimacs(s).unwrap_err().to_string();
+    assert_eq!(err("1 2 0\n"), "line 1: clauses before the 'p cnf' line");
+    assert_eq!(err("p cnf 2 1\n1 3 0\n"), "line 2: variable 3 is more than the 2 declared");
+    assert_eq!(err("p cnf 2 2\n1 2 0\n"), "line 2: 2 clauses declared but 1 found");
+    assert_eq!(err("p dnf 2 2\n"), "line 1: expected 'p cnf <variables> <clauses>'");
+    assert_eq!(err("p cnf 1 1\n1 x 0\n"), "line 2: 'x' isn't a literal");
+  }
+
+  #[test]
+  fn test_round_trip() {
+    for s in ["(p v ~q) ^ (q_1 v rain) ^ ~p", "p <-> (q ^ ~r)", "p ^ ~p"].iter() {
+      let e = parse(s);
+      let cnf = Cnf::from_expression(&e);
+      let read = Cnf::from_dimacs(&cnf.to_dimacs()).unwrap();
+      assert_eq!(read, cnf);
+      if e.is_cnf() {
+        assert!(are_equivalent(&read.to_expression("x"), &e).holds, "{}", s);
+      } else {
+        assert_eq!(read.solve().is_some(), e.is_satisfiable().holds, "{}", s);
+      }
+    }
+  }
+}
@@ -0,0 +1,410 @@
+//! Minimal two-level forms of truth functions, found with Quine–McCluskey and Petrick's method, and Karnaugh maps.
+//!
+//! A truth function of `n` sentence letters is given by its minterms: the numbers of the valuations that make it true,
+//! reading the letters in order as the bits of a binary number with the first letter most significant and true as 1.
+//! Don't-care minterms may be counted as true or false, whichever makes the result smaller.
+
+use super::truth_table::{self, TableFormat, TruthTable};
+use super::{Expression, Variable};
+use std::collections::{BTreeSet, HashSet};
+
+/// Beyond this many letters a Karnaugh map stops being something anyone would read.
+pub const MAX_KARNAUGH_VARIABLES: usize = 6;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub enum MinimalForm {
+  /// A disjunction of conjunctions of literals.
+  SumOfProducts,
+  /// A conjunction of disjunctions of literals.
+  ProductOfSums,
+}
+
+/// A conjunction of literals, as the minterms it covers: those agreeing with `value` on every bit not in `mask`.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub struct Implicant {
+  value: usize,
+  mask: usize,
+}
+
+impl Implicant {
+  pub fn covers(&self, minterm: usize) -> bool {
+    minterm & !self.mask == self.value
+  }
+
+  /// The value this requires of each of `n` letters, if any.
+  pub fn literals(&self, n: usize) -> Vec<Option<bool>> {
+    (0..n)
+      .map(|j| {
+        let bit = 1 << (n - 1 - j);
+        if self.mask & bit == 0 {
+          Some(self.value & bit != 0)
+        } else {
+          None
+        }
+      })
+      .collect()
+  }
+
+  fn size(&self, n: usize) -> usize {
+    n - self.mask.count_ones() as usize
+  }
+
+  /// The implicant covering both this and `other`, if they differ in exactly one letter.
+  fn merge(&self, other: &Self) -> Option<Self> {
+    let difference = self.value ^ other.value;
+    if self.mask == other.mask && difference.count_ones() == 1 {
+      Some(Self {
+        value: self.value & !difference,
+        mask: self.mask | difference,
+      })
+    } else {
+      None
+    }
+  }
+}
+
+/// The prime implicants of the function of `n` letters true on `minterms`, by Quine–McCluskey.
+pub fn prime_implicants(n: usize, minterms: &[usize]) -> Vec<Implicant> {
+  let mut current = minterms
+    .iter()
+    .map(|m| Implicant { value: *m, mask: 0 })
+    .collect::<BTreeSet<_>>();
+  let mut primes = BTreeSet::new();
+  while !current.is_empty() {
+    let mut merged = HashSet::new();
+    let mut next = BTreeSet::new();
+    for a in &current {
+      for b in current.range(a..) {
+        if let Some(c) = a.merge(b) {
+          merged.insert(*a);
+          merged.insert(*b);
+          next.insert(c);
+        }
+      }
+    }
+    primes.extend(current.into_iter().filter(|i| !merged.contains(i)));
+    current = next;
+  }
+  debug_assert!(primes.iter().all(|p| p.value < 1 << n));
+  primes.into_iter().collect()
+}
+
+/// A smallest set of prime implicants covering `ones`, where any of `dont_cares` may be covered or not.  Essential
+/// prime implicants are taken first and Petrick's method finds the rest; among covers with the fewest implicants, one
+/// with the fewest literals is chosen.
+pub fn minimal_cover(n: usize, ones: &[usize], dont_cares: &[usize]) -> Vec<Implicant> {
+  let ones = ones.iter().cloned().collect::<BTreeSet<_>>();
+  let minterms = ones.iter().chain(dont_cares).cloned().collect::<Vec<_>>();
+  let primes = prime_implicants(n, &minterms);
+  let mut chosen = BTreeSet::new();
+  for m in &ones {
+    let covering = (0..primes.len()).filter(|i| primes[*i].covers(*m)).collect::<Vec<_>>();
+    if covering.len() == 1 {
+      chosen.insert(covering[0]);
+    }
+  }
+  let uncovered = ones
+    .iter()
+    .filter(|m| !chosen.iter().any(|i| primes[*i].covers(**m)))
+    .collect::<Vec<_>>();
+
+  // Petrick's method: multiply out the product, over uncovered minterms, of the sum of the implicants covering each,
+  // keeping only products that aren't absorbed by another.
+  let mut products: Vec<BTreeSet<usize>> = vec![BTreeSet::new()];
+  for m in uncovered {
+    let mut next: Vec<BTreeSet<usize>> = Vec::new();
+    for product in &products {
+      for i in (0..primes.len()).filter(|i| primes[*i].covers(*m)) {
+        let mut p = product.clone();
+        p.insert(i);
+        if !next.iter().any(|q| q.is_subset(&p)) {
+          next.retain(|q| !p.is_subset(q));
+          next.push(p);
+        }
+      }
+    }
+    products = next;
+  }
+  let cost = |p: &BTreeSet<usize>| (p.len(), p.iter().map(|i| primes[*i].size(n)).sum::<usize>());
+  let best = products
+    .into_iter()
+    .min_by(|a, b| cost(a).cmp(&cost(b)).then_with(|| a.cmp(b)))
+    .expect("every minterm is covered by some prime implicant");
+  let mut cover = chosen.union(&best).map(|i| primes[*i]).collect::<Vec<_>>();
+  // Terms requiring letters to be true come first, then letters in order.
+  cover.sort_by_key(|i| i.literals(n).into_iter().map(|l| l.map_or(2, |t| !t as u8)).collect::<Vec<_>>());
+  cover
+}
+
+/// The minimal form of the function of `variables` true on `ones`, with `dont_cares` free.
+pub fn minimize_function(
+  variables: &[Variable],
+  ones: &[usize],
+  dont_cares: &[usize],
+  form: MinimalForm,
+) -> Expression {
+  let n = variables.len();
+  let literal = |v: Variable, positive: bool| {
+    let letter = Expression::Variable(v);
+    if positive {
+      letter
+    } else {
+      Expression::Negated(Box::new(letter))
+    }
+  };
+  let join = |parts: Vec<Expression>, conjunction: bool| {
+    let op = |a, b| {
+      if conjunction {
+        Expression::And(Box::new(a), Box::new(b))
+      } else {
+        Expression::Or(Box::new(a), Box::new(b))
+      }
+    };
+    parts.into_iter().reduce(op).unwrap_or(if conjunction {
+      Expression::Top
+    } else {
+      Expression::Bottom
+    })
+  };
+  let group = |i: &Implicant, positive: bool, conjunction: bool| {
+    let literals = i
+      .literals(n)
+      .into_iter()
+      .zip(variables)
+      .filter_map(|(l, v)| l.map(|t| literal(*v, t == positive)))
+      .collect();
+    join(literals, conjunction)
+  };
+  match form {
+    MinimalForm::SumOfProducts => {
+      let terms = minimal_cover(n, ones, dont_cares).iter().map(|i| group(i, true, true)).collect();
+      join(terms, false)
+    }
+    MinimalForm::ProductOfSums => {
+      // Each term of a minimal sum of products for the negation gives a clause, by De Morgan.
+      let zeros = (0..1 << n)
+        .filter(|m| !ones.contains(m) && !dont_cares.contains(m))
+        .collect::<Vec<_>>();
+      let clauses = minimal_cover(n, &zeros, dont_cares)
+        .iter()
+        .map(|i| group(i, false, false))
+        .collect();
+      join(clauses, true)
+    }
+  }
+}
+
+/// The letters of `e` in order, and the minterms on which it is true.
+fn minterms(e: &Expression) -> (Vec<Variable>, Vec<usize>) {
+  let mut variables = e.variables().into_iter().collect::<Vec<_>>();
+  variables.sort();
+  let n = variables.len();
+  let ones = (0..1 << n)
+    .filter(|m| {
+      let trues = (0..n).filter(|j| m & (1 << (n - 1 - j)) != 0).map(|j| variables[j]).collect();
+      e.eval(&trues)
+    })
+    .collect();
+  (variables, ones)
+}
+
+impl Expression {
+  /// An equivalent sum of products with as few terms, and then literals, as possible.
+  pub fn minimal_sop(&self) -> Self {
+    let (variables, ones) = minterms(self);
+    minimize_function(&variables, &ones, &[], MinimalForm::SumOfProducts)
+  }
+
+  /// An equivalent product of sums with as few clauses, and then literals, as possible.
+  pub fn minimal_pos(&self) -> Self {
+    let (variables, ones) = minterms(self);
+    minimize_function(&variables, &ones, &[], MinimalForm::ProductOfSums)
+  }
+}
+
+impl TruthTable {
+  /// The minimal form of the column for `e`, if `e` is one of the table's letters or columns.
+  pub fn minimize(&self, e: &Expression, form: MinimalForm) -> Option<Expression> {
+    let n = self.variables().len();
+    let mut ones = Vec::new();
+    for row in self.rows() {
+      if self.value(row, e)? {
+        ones.push(row.valuation.values().fold(0, |m, t| m << 1 | *t as usize));
+      }
+    }
+    debug_assert!(ones.iter().all(|m| *m < 1 << n));
+    Some(minimize_function(self.variables(), &ones, &[], form))
+  }
+}
+
+/// Letters on the rows and columns, each in Gray code order so that neighbouring cells differ in exactly one letter.
+/// With five or six letters, cells are also neighbours across the middle of each half.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct KarnaughMap {
+  row_variables: Vec<Variable>,
+  column_variables: Vec<Variable>,
+  /// `None` for a don't-care.
+  cells: Vec<Vec<Option<bool>>>,
+}
+
+fn gray_code(bits: usize) -> Vec<usize> {
+  (0..1 << bits).map(|i| i ^ (i >> 1)).collect()
+}
+
+impl KarnaughMap {
+  /// The map of `e`, if it has at most `MAX_KARNAUGH_VARIABLES` letters.
+  pub fn new(e: &Expression) -> Option<Self> {
+    if e.variables().len() > MAX_KARNAUGH_VARIABLES {
+      return None;
+    }
+    let (variables, ones) = minterms(e);
+    Self::from_minterms(&variables, &ones, &[])
+  }
+
+  pub fn from_minterms(variables: &[Variable], ones: &[usize], dont_cares: &[usize]) -> Option<Self> {
+    let n = variables.len();
+    if n > MAX_KARNAUGH_VARIABLES {
+      return None;
+    }
+    let row_bits = n / 2;
+    let column_bits = n - row_bits;
+    let cells = gray_code(row_bits)
+      .into_iter()
+      .map(|r| {
+        gray_code(column_bits)
+          .into_iter()
+          .map(|c| {
+            let m = r << column_bits | c;
+            if dont_cares.contains(&m) {
+              None
+            } else {
+              Some(ones.contains(&m))
+            }
+          })
+          .collect()
+      })
+      .collect();
+    Some(Self {
+      row_variables: variables[..row_bits].to_vec(),
+      column_variables: variables[row_bits..].to_vec(),
+      cells,
+    })
+  }
+
+  /// The value in each cell, rows and columns in Gray code order; `None` is a don't-care.
+  pub fn cells(&self) -> &[Vec<Option<bool>>] {
+    &self.cells
+  }
+
+  pub fn render(&self, format: TableFormat) -> String {
+    let label = |bits: usize, value: usize| {
+      (0..bits)
+        .map(|j| if value & (1 << (bits - 1 - j)) != 0 { '1' } else { '0' })
+        .collect::<String>()
+    };
+    let names = |vs: &[Variable]| {
+      let separator = if vs.iter().all(|v| v.name().chars().count() == 1) { "" } else { "," };
+      vs.iter().map(|v| v.name()).collect::<Vec<_>>().join(separator)
+    };
+    let (row_bits, column_bits) = (self.row_variables.len(), self.column_variables.len());
+    let mut header = vec![format!("{}\\{}", names(&self.row_variables), names(&self.column_variables))];
+    header.extend(gray_code(column_bits).into_iter().map(|c| label(column_bits, c)));
+    let cells = gray_code(row_bits)
+      .into_iter()
+      .zip(&self.cells)
+      .map(|(r, row)| {
+        let mut line = vec![label(row_bits, r)];
+        line.extend(row.iter().map(|c| match c {
+          Some(true) => "1".to_owned(),
+          Some(false) => "0".to_owned(),
+          None => "-".to_owned(),
+        }));
+        line
+      })
+      .collect::<Vec<_>>();
+    truth_table::render_grid(&header, &cells, format)
+  }
+}
+
+impl std::fmt::Display for KarnaughMap {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.render(TableFormat::Text))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+  use crate::logic::classical::semantics::are_equivalent;
+
+  fn parse(s: &str) -> Expression {
+    ClassicalParser::parse_expression(s).unwrap()
+  }
+
+  #[test]
+  fn test_prime_implicants() {
+    // The textbook example: f(a, b, c, d) = Σm(4, 8, 10, 11, 12, 15) + d(9, 14).
+    let primes = prime_implicants(4, &[4, 8, 9, 10, 11, 12, 14, 15]);
+    let shown = primes
+      .iter()
+      .map(|p| p.literals(4).iter().map(|l| l.map_or('-', |t| if t { '1' } else { '0' })).collect::<String>())
+      .collect::<BTreeSet<_>>();
+    let expected = ["-100", "10--", "1--0", "1-1-"].iter().map(|s| s.to_string()).collect::<BTreeSet<_>>();
+    assert_eq!(shown, expected);
+    assert_eq!(minimal_cover(4, &[4, 8, 10, 11, 12, 15], &[9, 14]).len(), 3);
+  }
+
+  #[test]
+  fn test_petrick() {
+    // Cyclic: every minterm is covered by two primes and none is essential.
+    let vs = ["a", "b", "c"].iter().map(|v| Variable::new(v)).collect::<Vec<_>>();
+    let e = minimize_function(&vs, &[0, 1, 2, 5, 6, 7], &[], MinimalForm::SumOfProducts);
+    assert_eq!(e.to_string(), "a ∧ c ∨ ¬a ∧ ¬b ∨ b ∧ ¬c");
+  }
+
+  #[test]
+  fn test_minimal_forms() {
+    let e = parse("(p -> q) ^ (~p -> q) v p ^ q ^ r");
+    assert_eq!(e.minimal_sop().to_string(), "q");
+    let e = parse("p ^ q v p ^ ~q ^ r v ~p ^ q ^ r");
+    assert_eq!(e.minimal_sop().to_string(), "p ∧ q ∨ p ∧ r ∨ q ∧ r");
+    assert_eq!(e.minimal_pos().to_string(), "(p ∨ q) ∧ (p ∨ r) ∧ (q ∨ r)");
+    assert_eq!(parse("p ^ ~p").minimal_sop(), Expression::Bottom);
+    assert_eq!(parse("p v ~p").minimal_pos(), Expression::Top);
+    for s in ["p <-> q", "~(p ^ (q v ~r)) <-> s", "(p -> q) -> r"].iter() {
+      let e = parse(s);
+      assert!(are_equivalent(&e, &e.minimal_sop()).holds, "{}", s);
+      assert!(are_equivalent(&e, &e.minimal_pos()).holds, "{}", s);
+      assert!(e.minimal_sop().is_dnf() && e.minimal_pos().is_cnf(), "{}", s);
+    }
+
+    let e = parse("p ^ q v r");
+    let table = TruthTable::new(std::slice::from_ref(&e));
+    assert_eq!(table.minimize(&e, MinimalForm::SumOfProducts), Some(e.minimal_sop()));
+    assert_eq!(table.minimize(&parse("q"), MinimalForm::ProductOfSums), Some(parse("q")));
+    assert_eq!(table.minimize(&parse("q v p"), MinimalForm::ProductOfSums), None);
+  }
+
+  #[test]
+  fn test_karnaugh_map() {
+    let map = KarnaughMap::new(&parse("p ^ ~r v q ^ s")).unwrap();
+    assert_eq!(
+      map.to_string(),
+      "pq\\rs | 00 | 01 | 11 | 10
+------+----+----+----+---
+00    | 0  | 0  | 0  | 0
+01    | 0  | 1  | 1  | 0
+11    | 1  | 1  | 1  | 0
+10    | 1  | 1  | 0  | 0"
+    );
+    let vs = [Variable::new("a"), Variable::new("b")];
+    let map = KarnaughMap::from_minterms(&vs, &[3], &[1]).unwrap();
+    assert_eq!(
+      map.render(TableFormat::Html),
+      "<table>\n  <tr><th>a\\b</th><th>0</th><th>1</th></tr>\n  <tr><td>0</td><td>0</td><td>-</td></tr>\n  <tr><td>1</td><td>0</td><td>1</td></tr>\n</table>"
+    );
+    assert_eq!(KarnaughMap::new(&parse("p1 ^ p2 ^ p3 ^ p4 ^ p5 ^ p6 ^ p7")), None);
+    assert_eq!(KarnaughMap::new(&parse("p1 ^ p2 ^ p3 ^ p4 ^ p5 ^ p6")).unwrap().cells().len(), 8);
+  }
+}
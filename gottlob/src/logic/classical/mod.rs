@@ -1,14 +1,30 @@
 use crate::util::powerset::IntoPowerSet;
 use std::collections::HashSet;
-use strum_macros::*;
-use log::*;
+use std::convert::TryFrom;
 
+pub mod completeness;
+pub mod countermodel;
+pub mod dimacs;
+pub mod minimize;
+pub mod normal_form;
 pub mod parser;
 pub mod parser_reverse_polish;
+pub mod sat;
+pub mod semantics;
+pub mod tableau;
+pub mod truth_table;
 
 use parser::ClassicalParser;
 use parser_reverse_polish::ClassicalRpParser;
-use super::{Logic, LogicResult, ParseError};
+use super::formula::{self, Formula, Language, Operator, Shape};
+use super::latex::Latex;
+use super::printer::{self, Style};
+use super::simplify::{self, Simplification};
+use super::{modal, Logic, LogicResult, ParseError};
+
+pub use super::formula::Variable;
+
+pub type Theorem = formula::Theorem<Expression>;
 
 pub struct ClassicalLogic;
 
@@ -18,45 +34,69 @@ impl Logic for ClassicalLogic {
   }
 
   fn is_valid_theorem(&self, t: &str) -> LogicResult {
-    // TODO: actually parse theorems, not just sentences
-    let thm = ClassicalParser::parse_expression(t).or_else(|_| ClassicalRpParser::parse_expression(t)).map_err(|e| {
-      // TODO: don't log these, since we expect them to be common; instead put better info in the error
-      error!("classical parse error: {:?}", e);
-      ParseError
-    })?;
-    Ok((format!("{}", thm), true))
+    let thm = parse(t)?;
+    Ok((format!("{}", thm), check(&thm)))
   }
 }
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
-pub struct Variable(char);
+/// Parses a theorem, or a lone sentence as the claim that it is a tautology.
+pub fn parse(t: &str) -> Result<Theorem, ParseError> {
+  if t.contains("|-") || t.contains("|/-") {
+    return ClassicalParser::parse_theorem(t);
+  }
+  // Errors from the infix parser are much more likely to be what the user needs to see.
+  let conclusion = ClassicalParser::parse_expression(t).or_else(|e| ClassicalRpParser::parse_expression(t).map_err(|_| e))?;
+  Ok(Theorem::Proves {
+    assumptions: Vec::new(),
+    conclusion,
+  })
+}
 
-impl std::fmt::Display for Variable {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    let Variable(v) = self;
-    write!(f, "{}", v)
+/// Decides whether a theorem's claim is correct, with `semantics::entails` rather than a tableau, which can take
+/// exponentially long on sentences a truth table or the SAT solver makes short work of.  A claim of non-entailment is
+/// only accepted once the countermodel found for it has been checked against the argument.
+pub fn check(thm: &Theorem) -> bool {
+  let verdict = semantics::entails(thm.assumptions(), thm.conclusion());
+  match thm {
+    Theorem::Proves { .. } => verdict.holds,
+    Theorem::DoesNotProve {
+      assumptions,
+      conclusion,
+    } => verdict
+      .witness()
+      .is_some_and(|w| assumptions.iter().all(|a| w.satisfies(a)) && !w.satisfies(conclusion)),
   }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, EnumDiscriminants)] // Maybe we don't want to derive Eq for these, but instead impl something.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)] // Maybe we don't want to derive Eq for these, but instead impl something.
 pub enum Expression {
   Variable(Variable),
+  Top,
+  Bottom,
   Negated(Box<Expression>),
   And(Box<Expression>, Box<Expression>),
   Or(Box<Expression>, Box<Expression>),
   Conditional(Box<Expression>, Box<Expression>),
   Biconditional(Box<Expression>, Box<Expression>),
+  Nand(Box<Expression>, Box<Expression>),
+  Nor(Box<Expression>, Box<Expression>),
+  Xor(Box<Expression>, Box<Expression>),
 }
 
 impl Expression {
   pub fn eval(&self, trues: &HashSet<Variable>) -> bool {
     match self {
       Self::Variable(v) => trues.contains(&v),
+      Self::Top => true,
+      Self::Bottom => false,
       Self::Negated(e) => !e.eval(trues),
       Self::And(e1, e2) => e1.eval(trues) && e2.eval(trues),
       Self::Or(e1, e2) => e1.eval(trues) || e2.eval(trues),
       Self::Conditional(e1, e2) => !e1.eval(trues) || e2.eval(trues),
       Self::Biconditional(e1, e2) => e1.eval(trues) == e2.eval(trues),
+      Self::Nand(e1, e2) => !(e1.eval(trues) && e2.eval(trues)),
+      Self::Nor(e1, e2) => !(e1.eval(trues) || e2.eval(trues)),
+      Self::Xor(e1, e2) => e1.eval(trues) != e2.eval(trues),
     }
   }
 
@@ -64,72 +104,127 @@ impl Expression {
   pub fn variables(&self) -> HashSet<Variable> {
     match self {
       Self::Variable(v) => [*v].into_iter().cloned().collect::<HashSet<Variable>>(),
+      Self::Top | Self::Bottom => HashSet::new(),
       Self::Negated(e) => e.variables(),
       Self::And(e1, e2) => e1.variables().union(&e2.variables()).cloned().collect(),
       Self::Or(e1, e2) => e1.variables().union(&e2.variables()).cloned().collect(),
       Self::Conditional(e1, e2) => e1.variables().union(&e2.variables()).cloned().collect(),
       Self::Biconditional(e1, e2) => e1.variables().union(&e2.variables()).cloned().collect(),
+      Self::Nand(e1, e2) => e1.variables().union(&e2.variables()).cloned().collect(),
+      Self::Nor(e1, e2) => e1.variables().union(&e2.variables()).cloned().collect(),
+      Self::Xor(e1, e2) => e1.variables().union(&e2.variables()).cloned().collect(),
     }
   }
 
-  /// Uses truth-table to determine if this is a tautology.  Very inefficient if there are many variables.
-  pub fn is_tautology(&self) -> bool {
-    self.variables().powerset().all(|sub| self.eval(&sub))
+  /// An equivalent sentence made with the rewrites in `simplify::RewriteRule`, together with each rewrite made.
+  pub fn simplify(&self) -> Simplification<Self> {
+    simplify::simplify(self)
   }
 
-  fn is_variable(&self) -> bool {
-    ExpressionDiscriminants::from(self) == ExpressionDiscriminants::Variable
+  /// Uses a truth table to determine if this is a tautology, or the SAT solver when there are more than
+  /// `semantics::SAT_THRESHOLD` variables.
+  pub fn is_tautology(&self) -> bool {
+    let variables = self.variables();
+    if variables.len() > semantics::SAT_THRESHOLD {
+      return sat::Cnf::tseitin(&[Self::Negated(Box::new(self.clone()))]).solve().is_none();
+    }
+    variables.powerset().all(|sub| self.eval(&sub))
   }
+}
 
-  fn is_negated(&self) -> bool {
-    ExpressionDiscriminants::from(self) == ExpressionDiscriminants::Negated
+impl Formula for Expression {
+  fn language() -> Language {
+    Language::Classical
   }
 
-  // fn is_and(&self) -> bool {
-  //   ExpressionDiscriminants::from(self) == ExpressionDiscriminants::And
-  // }
-
-  // fn is_or(&self) -> bool {
-  //   ExpressionDiscriminants::from(self) == ExpressionDiscriminants::Or
-  // }
+  fn shape(&self) -> Shape<&Self> {
+    match self {
+      Self::Variable(v) => Shape::Variable(*v),
+      Self::Top => Shape::Constant(true),
+      Self::Bottom => Shape::Constant(false),
+      Self::Negated(e) => Shape::Unary(Operator::Not, e),
+      Self::And(e1, e2) => Shape::Binary(Operator::And, e1, e2),
+      Self::Or(e1, e2) => Shape::Binary(Operator::Or, e1, e2),
+      Self::Conditional(e1, e2) => Shape::Binary(Operator::Conditional, e1, e2),
+      Self::Biconditional(e1, e2) => Shape::Binary(Operator::Biconditional, e1, e2),
+      Self::Nand(e1, e2) => Shape::Binary(Operator::Nand, e1, e2),
+      Self::Nor(e1, e2) => Shape::Binary(Operator::Nor, e1, e2),
+      Self::Xor(e1, e2) => Shape::Binary(Operator::Xor, e1, e2),
+    }
+  }
 
-  // fn is_conditional(&self) -> bool {
-  //   ExpressionDiscriminants::from(self) == ExpressionDiscriminants::Conditional
-  // }
+  fn from_shape(shape: Shape<Self>) -> Self {
+    match shape {
+      Shape::Variable(v) => Self::Variable(v),
+      Shape::Constant(true) => Self::Top,
+      Shape::Constant(false) => Self::Bottom,
+      Shape::Unary(Operator::Not, e) => Self::Negated(Box::new(e)),
+      Shape::Binary(Operator::And, e1, e2) => Self::And(Box::new(e1), Box::new(e2)),
+      Shape::Binary(Operator::Or, e1, e2) => Self::Or(Box::new(e1), Box::new(e2)),
+      Shape::Binary(Operator::Conditional, e1, e2) => Self::Conditional(Box::new(e1), Box::new(e2)),
+      Shape::Binary(Operator::Biconditional, e1, e2) => Self::Biconditional(Box::new(e1), Box::new(e2)),
+      Shape::Binary(Operator::Nand, e1, e2) => Self::Nand(Box::new(e1), Box::new(e2)),
+      Shape::Binary(Operator::Nor, e1, e2) => Self::Nor(Box::new(e1), Box::new(e2)),
+      Shape::Binary(Operator::Xor, e1, e2) => Self::Xor(Box::new(e1), Box::new(e2)),
+      shape => panic!("{:?} is not a classical operator", shape.operator()),
+    }
+  }
+}
 
-  // fn is_biconditional(&self) -> bool {
-  //   ExpressionDiscriminants::from(self) == ExpressionDiscriminants::Biconditional
-  // }
+/// Fails with the first operator that classical logic doesn't have.
+impl TryFrom<modal::Expression> for Expression {
+  type Error = Operator;
 
-  fn flatten_and(&self) -> Vec<&Self> {
-    match self {
-      Expression::And(e1, e2) => [e1.flatten_and(), e2.flatten_and()].concat(),
-      e => vec![e],
-    }
+  fn try_from(e: modal::Expression) -> Result<Self, Self::Error> {
+    e.convert()
   }
 }
 
 impl std::fmt::Display for Expression {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    match self {
-      // TODO: do these without automatically grouping them.
-      // TODO: no parens on outermost
-      // TODO: use cooler unicode chars
-      Self::Variable(v) => write!(f, "{}", v),
-      Self::Negated(e) if e.is_variable() || e.is_negated() => write!(f, "¬{}", e),
-      Self::Negated(e) => write!(f, "¬{}", e),  // TODO: do I need this after all?
-      e @ Self::And(_, _) => write!(
-        f,
-        "({})",
-        e.flatten_and()
-          .iter()
-          .map(|e| format!("{}", e))
-          .collect::<Vec<_>>()
-          .join(" ∧ ")
-      ),
-      Self::Or(e1, e2) => write!(f, "({} ∨ {})", e1, e2),
-      Self::Conditional(e1, e2) => write!(f, "({} → {})", e1, e2),
-      Self::Biconditional(e1, e2) => write!(f, "({} ↔ {})", e1, e2),
-    }
+    printer::write(self, Style::Unicode, f)
+  }
+}
+
+impl Latex for Expression {
+  fn latex(&self) -> String {
+    printer::print(self, Style::Latex)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_check_theorems() {
+    let holds = |t: &str| check(&parse(t).unwrap());
+    assert!(holds("p -> q, p |- q"));
+    assert!(holds("p -> q, q |/- p"));
+    assert!(holds("|- p v ~p"));
+    assert!(holds("p ^ ~p |- q"));
+    assert!(!holds("p -> q, q |- p"));
+    assert!(!holds("p -> q, p |/- q"));
+    assert!(!holds("|/- (p -> q) v (q -> p)"));
+
+    // Far too many branches for a tableau to get through.
+    let premises = (1..=13).map(|i| format!("a{} v b{}", i, i)).collect::<Vec<_>>().join(", ");
+    assert!(holds(&format!("{} |- (x ^ y) v ~x v ~y", premises)));
+    assert!(holds(&format!("{} |/- x ^ y", premises)));
+    let cnf = ClassicalParser::parse_expression("((p -> q) ^ (r v s)) <-> ((p ^ r) v (q <-> ~s))").unwrap();
+    assert!(holds(&format!("|/- ~({})", cnf.to_tseitin_cnf())));
+  }
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(parse("p ^ q").unwrap().to_string(), " ⊢ p ∧ q");
+    assert_eq!(parse("^ p q").unwrap().to_string(), " ⊢ p ∧ q");
+    assert_eq!(parse("p, q |/- p ^ r").unwrap().to_string(), "p, q ⊬ p ∧ r");
+    // Leftovers that the reverse Polish grammar used to ignore get the infix parser's error.
+    let e = parse("p q").unwrap_err();
+    assert_eq!(e.span(), 2..2);
+    assert!(e.message().starts_with("expected the end of the input"), "{}", e);
+    assert_eq!(parse("p q ^").unwrap_err().span(), 2..2);
+    assert_eq!(parse("p ^").unwrap_err().message(), "expected a sentence, found the end of the input");
   }
 }
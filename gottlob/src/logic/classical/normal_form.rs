@@ -0,0 +1,275 @@
+//! Negation, conjunctive and disjunctive normal forms, and the checks that a sentence is in one.
+//!
+//! Conjunctions and disjunctions are built to the left, which is how the parser groups them, so the results print
+//! without inner parentheses: `(p ∨ ¬q) ∧ r ∧ (q ∨ s)`.
+
+use super::sat::Cnf;
+use super::{Expression, Variable};
+use std::collections::HashSet;
+
+fn neg(e: Expression) -> Expression {
+  Expression::Negated(Box::new(e))
+}
+
+fn and(a: Expression, b: Expression) -> Expression {
+  Expression::And(Box::new(a), Box::new(b))
+}
+
+fn or(a: Expression, b: Expression) -> Expression {
+  Expression::Or(Box::new(a), Box::new(b))
+}
+
+/// `e` in negation normal form if `positive`, and `¬e` in negation normal form otherwise.
+fn nnf(e: &Expression, positive: bool) -> Expression {
+  use Expression::*;
+  match (e, positive) {
+    (Expression::Variable(_), true) => e.clone(),
+    (Expression::Variable(_), false) => neg(e.clone()),
+    (Top, true) | (Bottom, false) => Top,
+    (Top, false) | (Bottom, true) => Bottom,
+    (Negated(a), _) => nnf(a, !positive),
+    (And(a, b), true) => and(nnf(a, true), nnf(b, true)),
+    (And(a, b), false) => or(nnf(a, false), nnf(b, false)),
+    (Or(a, b), true) => or(nnf(a, true), nnf(b, true)),
+    (Or(a, b), false) => and(nnf(a, false), nnf(b, false)),
+    (Conditional(a, b), true) => or(nnf(a, false), nnf(b, true)),
+    (Conditional(a, b), false) => and(nnf(a, true), nnf(b, false)),
+    (Biconditional(a, b), true) => or(and(nnf(a, true), nnf(b, true)), and(nnf(a, false), nnf(b, false))),
+    (Biconditional(a, b), false) | (Xor(a, b), true) => {
+      or(and(nnf(a, true), nnf(b, false)), and(nnf(a, false), nnf(b, true)))
+    }
+    (Xor(a, b), false) => or(and(nnf(a, true), nnf(b, true)), and(nnf(a, false), nnf(b, false))),
+    (Nand(a, b), true) | (Nor(a, b), false) => or(nnf(a, !positive), nnf(b, !positive)),
+    (Nand(a, b), false) | (Nor(a, b), true) => and(nnf(a, !positive), nnf(b, !positive)),
+  }
+}
+
+/// Which normal form: a conjunction of disjunctions (clauses), or a disjunction of conjunctions (terms).
+#[derive(Eq, PartialEq, Clone, Copy)]
+enum Form {
+  Conjunctive,
+  Disjunctive,
+}
+
+/// Flattens a sentence in negation normal form into a list of clauses or terms, distributing as needed.  An empty list
+/// is `⊤` for CNF and `⊥` for DNF; an empty clause is `⊥` and an empty term `⊤`.
+fn groups(e: &Expression, form: Form) -> Vec<Vec<Expression>> {
+  use Expression::*;
+  match (e, form) {
+    (And(a, b), Form::Conjunctive) | (Or(a, b), Form::Disjunctive) => {
+      let mut joined = groups(a, form);
+      joined.extend(groups(b, form));
+      joined
+    }
+    (And(a, b), Form::Disjunctive) | (Or(a, b), Form::Conjunctive) => {
+      let (a, b) = (groups(a, form), groups(b, form));
+      a.iter().flat_map(|x| b.iter().map(move |y| [x.as_slice(), y.as_slice()].concat())).collect()
+    }
+    (Top, Form::Conjunctive) | (Bottom, Form::Disjunctive) => vec![],
+    (Top, Form::Disjunctive) | (Bottom, Form::Conjunctive) => vec![vec![]],
+    (literal, _) => vec![vec![literal.clone()]],
+  }
+}
+
+/// Removes repeated literals, clauses made trivial by a complementary pair, and repeated clauses, then builds the
+/// sentence back up.
+fn rebuild(groups: Vec<Vec<Expression>>, form: Form) -> Expression {
+  type Connective = fn(Expression, Expression) -> Expression;
+  let (outer, inner): (Connective, Connective) = match form {
+    Form::Conjunctive => (and, or),
+    Form::Disjunctive => (or, and),
+  };
+  let (unit, zero) = match form {
+    Form::Conjunctive => (Expression::Top, Expression::Bottom),
+    Form::Disjunctive => (Expression::Bottom, Expression::Top),
+  };
+  let mut kept: Vec<Vec<Expression>> = Vec::new();
+  for group in groups {
+    let mut literals: Vec<Expression> = Vec::new();
+    for l in group {
+      if !literals.contains(&l) {
+        literals.push(l);
+      }
+    }
+    if literals.iter().any(|l| literals.contains(&neg(l.clone()))) {
+      continue;
+    }
+    let set = literals.iter().collect::<HashSet<_>>();
+    if !kept.iter().any(|k| k.iter().collect::<HashSet<_>>() == set) {
+      kept.push(literals);
+    }
+  }
+  if kept.iter().any(|g| g.is_empty()) {
+    return zero;
+  }
+  kept
+    .into_iter()
+    .map(|g| g.into_iter().reduce(inner).expect("empty groups are handled above"))
+    .reduce(outer)
+    .unwrap_or(unit)
+}
+
+fn is_literal(e: &Expression) -> bool {
+  match e {
+    Expression::Variable(_) | Expression::Top | Expression::Bottom => true,
+    Expression::Negated(a) => matches!(a.as_ref(), Expression::Variable(_)),
+    _ => false,
+  }
+}
+
+/// Whether `e` is built from `groups` with the outer connective, each of which is built from literals with the inner
+/// one.
+fn is_normal(e: &Expression, form: Form) -> bool {
+  fn is_chain(e: &Expression, conjunction: bool, part: &dyn Fn(&Expression) -> bool) -> bool {
+    match (e, conjunction) {
+      (Expression::And(a, b), true) | (Expression::Or(a, b), false) => {
+        is_chain(a, conjunction, part) && is_chain(b, conjunction, part)
+      }
+      _ => part(e),
+    }
+  }
+  let conjunctive = form == Form::Conjunctive;
+  is_chain(e, conjunctive, &|group| is_chain(group, !conjunctive, &is_literal))
+}
+
+impl Cnf {
+  /// The clauses as a sentence.  Variables without a name are called `prefix` followed by a number, skipping names that
+  /// are already taken.
+  pub fn to_expression(&self, prefix: &str) -> Expression {
+    let used = self.names.iter().flatten().map(|v| v.name()).collect::<HashSet<_>>();
+    let mut fresh = (1..).map(|i| format!("{}{}", prefix, i)).filter(|name| !used.contains(name));
+    let names = self
+      .names
+      .iter()
+      .map(|name| name.unwrap_or_else(|| Variable::new(&fresh.next().unwrap())))
+      .collect::<Vec<_>>();
+    let clauses = self
+      .clauses
+      .iter()
+      .map(|c| {
+        c.iter()
+          .map(|l| {
+            let v = Expression::Variable(names[l.var()]);
+            if l.is_positive() {
+              v
+            } else {
+              neg(v)
+            }
+          })
+          .collect()
+      })
+      .collect();
+    rebuild(clauses, Form::Conjunctive)
+  }
+}
+
+impl Expression {
+  /// An equivalent sentence using only `¬`, `∧` and `∨`, with `¬` only in front of sentence letters.
+  pub fn to_nnf(&self) -> Self {
+    nnf(self, true)
+  }
+
+  /// An equivalent conjunction of disjunctions of literals.  This can be exponentially longer than the original.
+  pub fn to_cnf(&self) -> Self {
+    rebuild(groups(&self.to_nnf(), Form::Conjunctive), Form::Conjunctive)
+  }
+
+  /// An equivalent disjunction of conjunctions of literals.  This can be exponentially longer than the original.
+  pub fn to_dnf(&self) -> Self {
+    rebuild(groups(&self.to_nnf(), Form::Disjunctive), Form::Disjunctive)
+  }
+
+  /// A sentence in CNF that is satisfiable exactly when this one is, and only linearly longer.  It names each compound
+  /// subformula with a fresh letter `t1`, `t2`, ..., skipping any letter this sentence already uses.
+  pub fn to_tseitin_cnf(&self) -> Self {
+    Cnf::tseitin(std::slice::from_ref(self)).to_expression("t")
+  }
+
+  pub fn is_nnf(&self) -> bool {
+    match self {
+      Self::Variable(_) | Self::Top | Self::Bottom => true,
+      Self::Negated(a) => matches!(a.as_ref(), Self::Variable(_)),
+      Self::And(a, b) | Self::Or(a, b) => a.is_nnf() && b.is_nnf(),
+      Self::Conditional(_, _) | Self::Biconditional(_, _) | Self::Nand(_, _) | Self::Nor(_, _) | Self::Xor(_, _) => {
+        false
+      }
+    }
+  }
+
+  pub fn is_cnf(&self) -> bool {
+    is_normal(self, Form::Conjunctive)
+  }
+
+  pub fn is_dnf(&self) -> bool {
+    is_normal(self, Form::Disjunctive)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+  use crate::logic::classical::semantics::are_equivalent;
+
+  fn parse(s: &str) -> Expression {
+    ClassicalParser::parse_expression(s).unwrap()
+  }
+
+  const SENTENCES: [&str; 8] = [
+    "p",
+    "~~p",
+    "p -> q",
+    "~(p <-> q)",
+    "(p -> q) -> r",
+    "~(p ^ (q v ~r)) <-> s",
+    "p ^ ~p",
+    "(p v T) -> (q ^ F)",
+  ];
+
+  #[test]
+  fn test_conversions() {
+    assert_eq!(parse("~(p -> q ^ ~r)").to_nnf().to_string(), "p ∧ (¬q ∨ r)");
+    assert_eq!(parse("(p ^ q) v r").to_cnf().to_string(), "(p ∨ r) ∧ (q ∨ r)");
+    assert_eq!(parse("p ^ (q v r)").to_dnf().to_string(), "p ∧ q ∨ p ∧ r");
+    assert_eq!(parse("p ^ ~p").to_dnf(), Expression::Bottom);
+    assert_eq!(parse("p v ~p").to_cnf(), Expression::Top);
+    for s in SENTENCES.iter() {
+      let e = parse(s);
+      for (converted, check) in [
+        (e.to_nnf(), Expression::is_nnf as fn(&Expression) -> bool),
+        (e.to_cnf(), Expression::is_cnf),
+        (e.to_dnf(), Expression::is_dnf),
+      ] {
+        assert!(check(&converted), "{} from {}", converted, s);
+        assert!(are_equivalent(&e, &converted).holds, "{} from {}", converted, s);
+      }
+    }
+  }
+
+  #[test]
+  fn test_tseitin() {
+    for s in SENTENCES.iter() {
+      let e = parse(s);
+      let t = e.to_tseitin_cnf();
+      assert!(t.is_cnf(), "{}", t);
+      assert_eq!(t.is_satisfiable().holds, e.is_satisfiable().holds, "{}", s);
+    }
+    let t = parse("t1 ^ t2 -> p").to_tseitin_cnf();
+    assert!(t.variables().contains(&Variable::new("t4")));
+    assert!(!t.variables().contains(&Variable::new("t5")));
+  }
+
+  #[test]
+  fn test_predicates() {
+    assert!(parse("p ^ (~q v r)").is_nnf());
+    assert!(!parse("~(p ^ q)").is_nnf());
+    assert!(!parse("p -> q").is_nnf());
+    assert!(parse("(p v ~q) ^ r ^ (q v s)").is_cnf());
+    assert!(parse("p v q").is_cnf());
+    assert!(parse("p v q").is_dnf());
+    assert!(!parse("p ^ (q v r)").is_dnf());
+    assert!(!parse("(p ^ q) v r").is_cnf());
+    assert!(parse("(p ^ q) v r").is_dnf());
+    assert!(!parse("~~p").is_cnf());
+  }
+}
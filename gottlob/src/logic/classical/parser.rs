@@ -1,69 +1,24 @@
-use lazy_static::*;
-use pest::error::Error;
-use pest::prec_climber::{Assoc, Operator, PrecClimber};
-use pest::Parser;
-use pest_derive::*;
-use super::{Expression, Variable};
+use super::{Expression, Theorem};
+use crate::logic::parser::FormulaParser;
+use crate::logic::ParseError;
 
-lazy_static! {
-  static ref PREC_CLIMBER: PrecClimber<Rule> = {
-    PrecClimber::new(vec![
-      Operator::new(Rule::biconditional, Assoc::Left),
-      Operator::new(Rule::conditional, Assoc::Right),
-      Operator::new(Rule::or, Assoc::Left),
-      Operator::new(Rule::and, Assoc::Left),
-    ])
-  };
-}
-// TODO: consider `grammar_inline`
-#[derive(Parser)]
-#[grammar = "logic/classical/grammar.pest"]
+/// Parses classical sentences and theorems with the shared grammar, rejecting modal operators.
 pub struct ClassicalParser;
 
 impl ClassicalParser {
-  pub fn parse_expression(s: &str) -> Result<Expression, Error<Rule>> {
-    let expr = Self::parse(Rule::whole_expr, s)?.next().unwrap();
-    use pest::iterators::Pair;
-    use pest::iterators::Pairs;
-
-    // println!("{:#?}", expr);
-    fn with_prec(pairs: Pairs<Rule>) -> Expression {
-      PREC_CLIMBER.climb(
-        pairs,
-        |pair: Pair<Rule>| match pair.as_rule() {
-          Rule::expr => with_prec(pair.into_inner()),
-          Rule::term => with_prec(pair.into_inner()),
-          Rule::negated => Expression::Negated(Box::new(with_prec(pair.into_inner()))),
-          Rule::literal => {
-            let c = pair.as_str().chars().next().unwrap();
-            Expression::Variable(Variable(c))
-          }
-          Rule::grouped => with_prec(pair.into_inner()),
-          _ => {
-            // println!("pair {:#?}", pair);
-            unreachable!()
-          }
-        },
-        |lhs: Expression, op: Pair<Rule>, rhs: Expression| match op.as_rule() {
-          Rule::and => Expression::And(Box::new(lhs), Box::new(rhs)),
-          Rule::or => Expression::Or(Box::new(lhs), Box::new(rhs)),
-          Rule::conditional => Expression::Conditional(Box::new(lhs), Box::new(rhs)),
-          Rule::biconditional => Expression::Biconditional(Box::new(lhs), Box::new(rhs)),
-          _ => {
-            // println!("op {:#?}", op);
-            unreachable!()
-          }
-        },
-      )
-    }
+  pub fn parse_theorem(s: &str) -> Result<Theorem, ParseError> {
+    FormulaParser::parse_theorem(s)
+  }
 
-    Ok(with_prec(expr.into_inner()))
+  pub fn parse_expression(s: &str) -> Result<Expression, ParseError> {
+    FormulaParser::parse_expression(s)
   }
 }
 
 #[cfg(test)]
 mod test {
   use super::*;
+  use crate::logic::formula::Variable as Var;
   #[test]
   fn test_parens_or_no() {
     assert_eq!(
@@ -85,28 +40,28 @@ mod test {
         Box::new(Conditional(
           Box::new(Or(
             Box::new(And(
-              Box::new(Variable(super::Variable('a'))),
-              Box::new(Variable(super::Variable('b')))
+              Box::new(Variable(Var::new("a"))),
+              Box::new(Variable(Var::new("b")))
             )),
-            Box::new(Variable(super::Variable('c')))
+            Box::new(Variable(Var::new("c")))
           )),
-          Box::new(Variable(super::Variable('d')))
+          Box::new(Variable(Var::new("d")))
         )),
-        Box::new(Variable(super::Variable('e')))
+        Box::new(Variable(Var::new("e")))
       ),
       "correct precedence"
     );
     assert_eq!(
       ClassicalParser::parse_expression("a <-> b -> c v d ^ e").unwrap(),
       Biconditional(
-        Box::new(Variable(super::Variable('a'))),
+        Box::new(Variable(Var::new("a"))),
         Box::new(Conditional(
-          Box::new(Variable(super::Variable('b'))),
+          Box::new(Variable(Var::new("b"))),
           Box::new(Or(
-            Box::new(Variable(super::Variable('c'))),
+            Box::new(Variable(Var::new("c"))),
             Box::new(And(
-              Box::new(Variable(super::Variable('d'))),
-              Box::new(Variable(super::Variable('e')))
+              Box::new(Variable(Var::new("d"))),
+              Box::new(Variable(Var::new("e")))
             )),
           )),
         )),
@@ -124,6 +79,58 @@ mod test {
   #[test]
   fn test_grouping_overrides_ooo() {}
 
+  #[test]
+  fn test_multi_character_variables() {
+    use Expression::*;
+    assert_eq!(
+      ClassicalParser::parse_expression("p1 ^ q_12 -> rain").unwrap(),
+      Conditional(
+        Box::new(And(Box::new(Variable(Var::new("p1"))), Box::new(Variable(Var::new("q_12"))))),
+        Box::new(Variable(Var::new("rain")))
+      )
+    );
+    assert_eq!(
+      ClassicalParser::parse_expression("vacation v victory").unwrap(),
+      Or(Box::new(Variable(Var::new("vacation"))), Box::new(Variable(Var::new("victory"))))
+    );
+    assert_eq!(ClassicalParser::parse_expression("pvq").unwrap(), Variable(Var::new("pvq")));
+    assert_eq!(
+      ClassicalParser::parse_expression("p v q").unwrap(),
+      ClassicalParser::parse_expression("(p)v(q)").unwrap()
+    );
+    assert!(ClassicalParser::parse_expression("v").is_err());
+    assert!(ClassicalParser::parse_expression("p v").is_err());
+    assert!(ClassicalParser::parse_expression("P").is_err(), "letters start lowercase");
+  }
+
+  #[test]
+  fn test_word_connectives() {
+    assert_eq!(
+      ClassicalParser::parse_expression("not p and q or r then s iff t").unwrap(),
+      ClassicalParser::parse_expression("~p ^ q v r -> s <-> t").unwrap()
+    );
+    assert_eq!(
+      ClassicalParser::parse_expression("notion and andrew").unwrap(),
+      ClassicalParser::parse_expression("notion ^ andrew").unwrap()
+    );
+  }
+
+  #[test]
+  fn test_constants() {
+    use Expression::*;
+    for (top, bottom) in [("T", "F"), ("true", "false"), ("⊤", "⊥")] {
+      assert_eq!(
+        ClassicalParser::parse_expression(&format!("{} ^ ~{}", top, bottom)).unwrap(),
+        And(Box::new(Top), Box::new(Negated(Box::new(Bottom))))
+      );
+    }
+    assert_eq!(ClassicalParser::parse_expression("truth v falsehood").unwrap().to_string(), "truth ∨ falsehood");
+    assert_eq!(ClassicalParser::parse_expression("T -> p").unwrap().to_string(), "⊤ → p");
+    assert!(ClassicalParser::parse_expression("Tp").is_err());
+    assert!(ClassicalParser::parse_expression("p v ~p <-> T").unwrap().is_tautology());
+    assert!(!ClassicalParser::parse_expression("p -> F").unwrap().is_tautology());
+  }
+
   #[test]
   fn test_some_tautologies() {
     assert!(ClassicalParser::parse_expression("p ^ q -> p")
@@ -139,4 +146,18 @@ mod test {
     println!("expr {:#?}", expr);
     assert!(expr.is_tautology())
   }
+
+  #[test]
+  fn test_parse_theorem() {
+    let thm = ClassicalParser::parse_theorem("p -> q, p |- q").unwrap();
+    assert_eq!(thm.assumptions().len(), 2);
+    assert_eq!(thm.conclusion(), &ClassicalParser::parse_expression("q").unwrap());
+    assert!(matches!(thm, Theorem::Proves { .. }));
+
+    let thm = ClassicalParser::parse_theorem("|/- p ^ ~p").unwrap();
+    assert!(thm.assumptions().is_empty());
+    assert!(matches!(thm, Theorem::DoesNotProve { .. }));
+
+    assert!(ClassicalParser::parse_theorem("[]p |- p").is_err());
+  }
 }
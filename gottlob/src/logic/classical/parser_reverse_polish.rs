@@ -1,23 +1,27 @@
-use pest::error::Error;
 use pest::Parser;
 use pest_derive::*;
 use super::{Expression, Variable};
+use crate::logic::{ParseError, RuleDescription};
 
 #[derive(Parser)]
 #[grammar = "logic/classical/grammar_reverse_polish.pest"]
 pub struct ClassicalRpParser;
 
 impl ClassicalRpParser {
-  pub fn parse_expression(s: &str) -> Result<Expression, Error<Rule>> {
-    let expr = Self::parse(Rule::expression, s)?.next().unwrap();
+  pub fn parse_expression(s: &str) -> Result<Expression, ParseError> {
+    let expr = Self::parse(Rule::whole_expression, s)
+      .map_err(|e| ParseError::from_pest(s, e))?
+      .next()
+      .unwrap();
     use pest::iterators::Pair;
 
     fn parse_value(p: Pair<Rule>) -> Expression {
       match p.as_rule() {
         Rule::literal => {
-          let c = p.as_str().chars().next().unwrap();
-          Expression::Variable(Variable(c))
+          Expression::Variable(Variable::new(p.as_str()))
         }
+        Rule::top => Expression::Top,
+        Rule::bottom => Expression::Bottom,
         Rule::negated => Expression::Negated(Box::new(parse_value(
           p.into_inner().next().expect("Negated has inner"),
         ))),
@@ -37,13 +41,27 @@ impl ClassicalRpParser {
           let (left, right) = parse_two_inner(p);
           Expression::Biconditional(left, right)
         }
+        Rule::nand => {
+          let (left, right) = parse_two_inner(p);
+          Expression::Nand(left, right)
+        }
+        Rule::nor => {
+          let (left, right) = parse_two_inner(p);
+          Expression::Nor(left, right)
+        }
+        Rule::xor => {
+          let (left, right) = parse_two_inner(p);
+          Expression::Xor(left, right)
+        }
         Rule::expression => parse_value(p.into_inner().next().unwrap()),
-        Rule::WHITESPACE => unreachable!(),
+        Rule::or_op | Rule::nand_op | Rule::nor_op | Rule::xor_op => unreachable!(),
+        Rule::WHITESPACE | Rule::identifier_char | Rule::whole_expression | Rule::EOI => unreachable!(),
       }
     }
 
     fn parse_two_inner(p: Pair<Rule>) -> (Box<Expression>, Box<Expression>) {
-      let mut inner = p.into_inner();
+      // Skip the operator, which the word operators have as a token of its own.
+      let mut inner = p.into_inner().filter(|p| p.as_rule() == Rule::expression);
       let left = Box::new(parse_value(inner.next().unwrap()));
       let right = Box::new(parse_value(inner.next().unwrap()));
       (left, right)
@@ -51,4 +69,67 @@ impl ClassicalRpParser {
 
     Ok(parse_value(expr))
   }
-}
\ No newline at end of file
+}
+impl RuleDescription for Rule {
+  fn description(&self) -> &'static str {
+    match self {
+      Self::expression | Self::whole_expression => "a sentence",
+      Self::literal => "a sentence letter",
+      Self::top => "'T'",
+      Self::bottom => "'F'",
+      Self::negated => "'~'",
+      Self::and => "'^'",
+      Self::or | Self::or_op => "'v'",
+      Self::conditional => "'->'",
+      Self::biconditional => "'<->'",
+      Self::nand | Self::nand_op => "'nand'",
+      Self::nor | Self::nor_op => "'nor'",
+      Self::xor | Self::xor_op => "'xor'",
+      Self::WHITESPACE => "a space",
+      Self::identifier_char => "a letter, digit or underscore",
+      Self::EOI => "the end of the input",
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+
+  #[test]
+  fn test_reverse_polish() {
+    assert_eq!(
+      ClassicalRpParser::parse_expression("-> ^ p1 q ~ v rain r").unwrap(),
+      ClassicalParser::parse_expression("p1 ^ q -> ~(rain v r)").unwrap()
+    );
+    assert_eq!(
+      ClassicalRpParser::parse_expression("v T ^ false ⊥").unwrap(),
+      ClassicalParser::parse_expression("T v (false ^ ⊥)").unwrap()
+    );
+    assert_eq!(
+      ClassicalRpParser::parse_expression("nand xor p q nor q ↑ p p").unwrap(),
+      ClassicalParser::parse_expression("(p xor q) nand (q nor (p nand p))").unwrap()
+    );
+  }
+
+  #[test]
+  fn test_trailing_input() {
+    let e = ClassicalRpParser::parse_expression("p q").unwrap_err();
+    assert_eq!(e.span(), 2..2);
+    assert_eq!(e.message(), "expected the end of the input, found 'q'");
+    assert!(ClassicalRpParser::parse_expression("^ p").is_err());
+    assert!(ClassicalRpParser::parse_expression("^ p q r").is_err());
+  }
+
+  #[test]
+  fn test_letters_starting_with_operators() {
+    let parse = |s: &str| ClassicalRpParser::parse_expression(s).unwrap();
+    assert_eq!(parse("^ vacation p"), ClassicalParser::parse_expression("vacation ^ p").unwrap());
+    assert_eq!(parse("v nandy nory"), ClassicalParser::parse_expression("nandy v nory").unwrap());
+    assert_eq!(parse("^ xory p"), ClassicalParser::parse_expression("xory ^ p").unwrap());
+    assert_eq!(parse("nand nory ↑ p nandy"), ClassicalParser::parse_expression("nory nand (p nand nandy)").unwrap());
+    assert_eq!(parse("vp"), Expression::Variable(Variable::new("vp")));
+    assert!(ClassicalRpParser::parse_expression("vp q").is_err(), "vp is a letter, not v applied to p");
+  }
+}
@@ -0,0 +1,553 @@
+//! A conflict-driven clause-learning SAT solver, for classical problems too big for truth tables or tableaux.
+//!
+//! Sentences are first put into clausal form with the Tseitin encoding, which gives every compound subformula a fresh
+//! variable and so stays linear in the size of the input.  The solver is the usual design: two watched literals per
+//! clause for unit propagation, first-UIP clause learning with non-chronological backjumping, activity-based
+//! decisions with saved phases, and Luby restarts.
+
+use super::semantics::Valuation;
+use super::{Expression, Variable};
+use std::collections::HashMap;
+
+/// A variable of a `Cnf` or its negation.  Variables are numbered from 0.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub struct Literal(usize);
+
+impl Literal {
+  pub fn new(var: usize, positive: bool) -> Self {
+    Self(var * 2 + if positive { 0 } else { 1 })
+  }
+
+  pub fn var(self) -> usize {
+    self.0 / 2
+  }
+
+  pub fn is_positive(self) -> bool {
+    self.0 & 1 == 0
+  }
+
+  pub fn negate(self) -> Self {
+    Self(self.0 ^ 1)
+  }
+
+  fn index(self) -> usize {
+    self.0
+  }
+}
+
+impl std::ops::Not for Literal {
+  type Output = Self;
+
+  fn not(self) -> Self {
+    self.negate()
+  }
+}
+
+/// A conjunction of clauses, each a disjunction of literals.  Variables that stand for a sentence letter remember its
+/// name; the rest were introduced by the encoding.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct Cnf {
+  pub names: Vec<Option<Variable>>,
+  pub clauses: Vec<Vec<Literal>>,
+}
+
+impl Cnf {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn num_variables(&self) -> usize {
+    self.names.len()
+  }
+
+  pub fn add_variable(&mut self, name: Option<Variable>) -> usize {
+    self.names.push(name);
+    self.names.len() - 1
+  }
+
+  pub fn add_clause(&mut self, clause: Vec<Literal>) {
+    self.clauses.push(clause);
+  }
+
+  /// The Tseitin encoding of `sentences`, which is satisfiable exactly when they are jointly satisfiable.  Sentence
+  /// letters come first, in alphabetical order.
+  pub fn tseitin(sentences: &[Expression]) -> Self {
+    let mut encoder = Encoder::default();
+    let mut letters = sentences.iter().flat_map(|s| s.variables()).collect::<Vec<_>>();
+    letters.sort();
+    letters.dedup();
+    for v in letters {
+      let var = encoder.cnf.add_variable(Some(v));
+      encoder.letters.insert(v, var);
+    }
+    for s in sentences {
+      let lit = encoder.encode(s);
+      encoder.cnf.add_clause(vec![lit]);
+    }
+    encoder.cnf
+  }
+
+  /// An assignment to every variable satisfying every clause, if there is one.
+  pub fn solve(&self) -> Option<Vec<bool>> {
+    let mut solver = Solver::new(self.num_variables());
+    for clause in &self.clauses {
+      solver.add_clause(clause.clone());
+    }
+    solver.solve()
+  }
+
+  /// The values a solution gives the sentence letters.
+  pub fn valuation(&self, solution: &[bool]) -> Valuation {
+    let valuation = self
+      .names
+      .iter()
+      .zip(solution)
+      .filter_map(|(name, t)| name.map(|v| (v, *t)))
+      .collect();
+    Valuation(valuation)
+  }
+}
+
+#[derive(Default)]
+struct Encoder<'a> {
+  cnf: Cnf,
+  letters: HashMap<Variable, usize>,
+  subformulas: HashMap<&'a Expression, Literal>,
+  top: Option<Literal>,
+}
+
+impl<'a> Encoder<'a> {
+  /// A literal equivalent to `e`, adding the clauses that define it.
+  fn encode(&mut self, e: &'a Expression) -> Literal {
+    if let Some(lit) = self.subformulas.get(e) {
+      return *lit;
+    }
+    let lit = match e {
+      Expression::Variable(v) => Literal::new(self.letters[v], true),
+      Expression::Top => self.top(),
+      Expression::Bottom => !self.top(),
+      Expression::Negated(a) => !self.encode(a),
+      Expression::And(a, b) => {
+        let (a, b, x) = self.binary(a, b);
+        self.define(x, &[&[!a, !b]], &[&[a], &[b]])
+      }
+      Expression::Or(a, b) => {
+        let (a, b, x) = self.binary(a, b);
+        self.define(x, &[&[!a], &[!b]], &[&[a, b]])
+      }
+      Expression::Conditional(a, b) => {
+        let (a, b, x) = self.binary(a, b);
+        self.define(x, &[&[a], &[!b]], &[&[!a, b]])
+      }
+      Expression::Biconditional(a, b) => {
+        let (a, b, x) = self.binary(a, b);
+        self.define(x, &[&[a, b], &[!a, !b]], &[&[!a, b], &[a, !b]])
+      }
+      Expression::Nand(a, b) => {
+        let (a, b, x) = self.binary(a, b);
+        self.define(x, &[&[a], &[b]], &[&[!a, !b]])
+      }
+      Expression::Nor(a, b) => {
+        let (a, b, x) = self.binary(a, b);
+        self.define(x, &[&[a, b]], &[&[!a], &[!b]])
+      }
+      Expression::Xor(a, b) => {
+        let (a, b, x) = self.binary(a, b);
+        self.define(x, &[&[!a, b], &[a, !b]], &[&[a, b], &[!a, !b]])
+      }
+    };
+    self.subformulas.insert(e, lit);
+    lit
+  }
+
+  fn binary(&mut self, a: &'a Expression, b: &'a Expression) -> (Literal, Literal, Literal) {
+    let a = self.encode(a);
+    let b = self.encode(b);
+    let x = Literal::new(self.cnf.add_variable(None), true);
+    (a, b, x)
+  }
+
+  /// Adds `x ∨ c` for each clause `c` in `if_false` and `¬x ∨ c` for each in `if_true`.
+  fn define(&mut self, x: Literal, if_false: &[&[Literal]], if_true: &[&[Literal]]) -> Literal {
+    for c in if_false {
+      self.cnf.add_clause([&[x], *c].concat());
+    }
+    for c in if_true {
+      self.cnf.add_clause([&[!x], *c].concat());
+    }
+    x
+  }
+
+  fn top(&mut self) -> Literal {
+    if let Some(top) = self.top {
+      return top;
+    }
+    let top = Literal::new(self.cnf.add_variable(None), true);
+    self.cnf.add_clause(vec![top]);
+    self.top = Some(top);
+    top
+  }
+}
+
+const RESTART_UNIT: usize = 100;
+const ACTIVITY_DECAY: f64 = 0.95;
+
+struct Solver {
+  clauses: Vec<Vec<Literal>>,
+  /// For each literal, the clauses watching it.  The watched literals of a clause are its first two.
+  watches: Vec<Vec<usize>>,
+  assignment: Vec<Option<bool>>,
+  level: Vec<usize>,
+  /// The clause that forced each variable, if it wasn't a decision.  The forced literal is that clause's first.
+  reason: Vec<Option<usize>>,
+  trail: Vec<Literal>,
+  /// Where each decision level starts on the trail.
+  trail_limits: Vec<usize>,
+  propagated: usize,
+  activity: Vec<f64>,
+  activity_increment: f64,
+  phase: Vec<bool>,
+  /// False once the clauses are known to be unsatisfiable.
+  ok: bool,
+}
+
+impl Solver {
+  fn new(num_variables: usize) -> Self {
+    Self {
+      clauses: Vec::new(),
+      watches: vec![Vec::new(); num_variables * 2],
+      assignment: vec![None; num_variables],
+      level: vec![0; num_variables],
+      reason: vec![None; num_variables],
+      trail: Vec::new(),
+      trail_limits: Vec::new(),
+      propagated: 0,
+      activity: vec![0.0; num_variables],
+      activity_increment: 1.0,
+      phase: vec![false; num_variables],
+      ok: true,
+    }
+  }
+
+  fn value(&self, lit: Literal) -> Option<bool> {
+    self.assignment[lit.var()].map(|t| t == lit.is_positive())
+  }
+
+  fn decision_level(&self) -> usize {
+    self.trail_limits.len()
+  }
+
+  /// Only called before solving, at level 0.
+  fn add_clause(&mut self, mut clause: Vec<Literal>) {
+    clause.sort();
+    clause.dedup();
+    if clause.windows(2).any(|w| w[0] == !w[1]) {
+      return;
+    }
+    clause.retain(|l| self.value(*l) != Some(false));
+    if clause.iter().any(|l| self.value(*l) == Some(true)) {
+      return;
+    }
+    match clause.len() {
+      0 => self.ok = false,
+      1 => self.assign(clause[0], None),
+      _ => {
+        self.watch(self.clauses.len(), &clause);
+        self.clauses.push(clause);
+      }
+    }
+  }
+
+  fn watch(&mut self, index: usize, clause: &[Literal]) {
+    self.watches[clause[0].index()].push(index);
+    self.watches[clause[1].index()].push(index);
+  }
+
+  fn assign(&mut self, lit: Literal, reason: Option<usize>) {
+    let var = lit.var();
+    self.assignment[var] = Some(lit.is_positive());
+    self.level[var] = self.decision_level();
+    self.reason[var] = reason;
+    self.trail.push(lit);
+  }
+
+  /// Unit propagation, giving back a clause made false if there is one.
+  fn propagate(&mut self) -> Option<usize> {
+    while self.propagated < self.trail.len() {
+      let falsified = !self.trail[self.propagated];
+      self.propagated += 1;
+      let mut watching = std::mem::take(&mut self.watches[falsified.index()]);
+      let mut conflict = None;
+      let mut i = 0;
+      while i < watching.len() {
+        let c = watching[i];
+        if self.clauses[c][0] == falsified {
+          self.clauses[c].swap(0, 1);
+        }
+        let first = self.clauses[c][0];
+        if self.value(first) == Some(true) {
+          i += 1;
+          continue;
+        }
+        let replacement = (2..self.clauses[c].len()).find(|k| self.value(self.clauses[c][*k]) != Some(false));
+        if let Some(k) = replacement {
+          self.clauses[c].swap(1, k);
+          self.watches[self.clauses[c][1].index()].push(c);
+          watching.swap_remove(i);
+          continue;
+        }
+        if self.value(first) == Some(false) {
+          conflict = Some(c);
+          break;
+        }
+        self.assign(first, Some(c));
+        i += 1;
+      }
+      self.watches[falsified.index()] = watching;
+      if conflict.is_some() {
+        self.propagated = self.trail.len();
+        return conflict;
+      }
+    }
+    None
+  }
+
+  /// Learns a clause from a conflict by resolving back to the first unique implication point.  Gives back the clause,
+  /// asserting literal first, and the level to jump back to.
+  fn analyze(&mut self, conflict: usize) -> (Vec<Literal>, usize) {
+    let mut seen = vec![false; self.assignment.len()];
+    let mut learnt = vec![];
+    let mut pending = 0;
+    let mut clause = conflict;
+    let mut skip_first = false;
+    let mut index = self.trail.len();
+    let asserting = loop {
+      for k in (if skip_first { 1 } else { 0 })..self.clauses[clause].len() {
+        let q = self.clauses[clause][k];
+        let var = q.var();
+        if seen[var] || self.level[var] == 0 {
+          continue;
+        }
+        seen[var] = true;
+        self.bump(var);
+        if self.level[var] == self.decision_level() {
+          pending += 1;
+        } else {
+          learnt.push(q);
+        }
+      }
+      loop {
+        index -= 1;
+        if seen[self.trail[index].var()] {
+          break;
+        }
+      }
+      let p = self.trail[index];
+      seen[p.var()] = false;
+      pending -= 1;
+      if pending == 0 {
+        break p;
+      }
+      clause = self.reason[p.var()].expect("only decisions lack a reason, and there is one per level");
+      skip_first = true;
+    };
+    learnt.insert(0, !asserting);
+    let mut backjump = 0;
+    if learnt.len() > 1 {
+      let (k, level) = (1..learnt.len())
+        .map(|k| (k, self.level[learnt[k].var()]))
+        .max_by_key(|(_, level)| *level)
+        .unwrap();
+      learnt.swap(1, k);
+      backjump = level;
+    }
+    self.activity_increment /= ACTIVITY_DECAY;
+    (learnt, backjump)
+  }
+
+  fn bump(&mut self, var: usize) {
+    self.activity[var] += self.activity_increment;
+    if self.activity[var] > 1e100 {
+      for a in &mut self.activity {
+        *a *= 1e-100;
+      }
+      self.activity_increment *= 1e-100;
+    }
+  }
+
+  fn backtrack(&mut self, level: usize) {
+    if self.decision_level() <= level {
+      return;
+    }
+    let start = self.trail_limits[level];
+    for lit in self.trail.drain(start..) {
+      self.phase[lit.var()] = lit.is_positive();
+      self.assignment[lit.var()] = None;
+      self.reason[lit.var()] = None;
+    }
+    self.trail_limits.truncate(level);
+    self.propagated = self.trail.len();
+  }
+
+  fn decide(&mut self) -> Option<Literal> {
+    let var = (0..self.assignment.len())
+      .filter(|v| self.assignment[*v].is_none())
+      .max_by(|a, b| self.activity[*a].partial_cmp(&self.activity[*b]).unwrap().then(b.cmp(a)))?;
+    Some(Literal::new(var, self.phase[var]))
+  }
+
+  fn solve(mut self) -> Option<Vec<bool>> {
+    if !self.ok {
+      return None;
+    }
+    let mut restarts = 0;
+    let mut conflicts = 0;
+    loop {
+      if let Some(conflict) = self.propagate() {
+        if self.decision_level() == 0 {
+          return None;
+        }
+        let (learnt, level) = self.analyze(conflict);
+        self.backtrack(level);
+        if learnt.len() == 1 {
+          self.assign(learnt[0], None);
+        } else {
+          let index = self.clauses.len();
+          self.watch(index, &learnt);
+          let asserting = learnt[0];
+          self.clauses.push(learnt);
+          self.assign(asserting, Some(index));
+        }
+        conflicts += 1;
+        if conflicts >= luby(restarts) * RESTART_UNIT {
+          conflicts = 0;
+          restarts += 1;
+          self.backtrack(0);
+        }
+        continue;
+      }
+      match self.decide() {
+        Some(lit) => {
+          self.trail_limits.push(self.trail.len());
+          self.assign(lit, None);
+        }
+        None => return Some(self.assignment.into_iter().map(|t| t.unwrap_or(false)).collect()),
+      }
+    }
+  }
+}
+
+/// The Luby sequence 1, 1, 2, 1, 1, 2, 4, 1, ..., counted from 0.
+fn luby(i: usize) -> usize {
+  let mut i = i + 1;
+  loop {
+    let k = usize::BITS - i.leading_zeros();
+    if i == (1 << k) - 1 {
+      return 1 << (k - 1);
+    }
+    i -= (1 << (k - 1)) - 1;
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+
+  fn parse(s: &str) -> Expression {
+    ClassicalParser::parse_expression(s).unwrap()
+  }
+
+  fn lit(i: i32) -> Literal {
+    Literal::new(i.unsigned_abs() as usize - 1, i > 0)
+  }
+
+  fn cnf(num_variables: usize, clauses: &[&[i32]]) -> Cnf {
+    Cnf {
+      names: vec![None; num_variables],
+      clauses: clauses.iter().map(|c| c.iter().map(|i| lit(*i)).collect()).collect(),
+    }
+  }
+
+  fn satisfies(cnf: &Cnf, solution: &[bool]) -> bool {
+    cnf
+      .clauses
+      .iter()
+      .all(|c| c.iter().any(|l| solution[l.var()] == l.is_positive()))
+  }
+
+  #[test]
+  fn test_luby() {
+    assert_eq!((0..15).map(luby).collect::<Vec<_>>(), vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+  }
+
+  #[test]
+  fn test_clauses() {
+    let sat = cnf(3, &[&[1, 2], &[-1, 3], &[-2, 3], &[-3, 1]]);
+    assert!(satisfies(&sat, &sat.solve().unwrap()));
+    assert_eq!(cnf(2, &[&[1, 2], &[-1, 2], &[1, -2], &[-1, -2]]).solve(), None);
+    assert_eq!(cnf(1, &[&[]]).solve(), None);
+    assert_eq!(cnf(0, &[]).solve(), Some(vec![]));
+  }
+
+  /// Every way of putting n + 1 pigeons in n holes doubles a hole up, which takes real clause learning to see.
+  fn pigeonhole(holes: usize) -> Cnf {
+    let var = |pigeon: usize, hole: usize| (pigeon * holes + hole) as i32 + 1;
+    let mut clauses = Vec::new();
+    for pigeon in 0..=holes {
+      clauses.push((0..holes).map(|h| var(pigeon, h)).collect::<Vec<_>>());
+    }
+    for hole in 0..holes {
+      for a in 0..=holes {
+        for b in a + 1..=holes {
+          clauses.push(vec![-var(a, hole), -var(b, hole)]);
+        }
+      }
+    }
+    let clauses = clauses.iter().map(|c| c.as_slice()).collect::<Vec<_>>();
+    cnf((holes + 1) * holes, &clauses)
+  }
+
+  #[test]
+  fn test_pigeonhole() {
+    assert_eq!(pigeonhole(6).solve(), None);
+    let mut fits = pigeonhole(6);
+    fits.clauses.remove(0);
+    assert!(satisfies(&fits, &fits.solve().unwrap()));
+  }
+
+  #[test]
+  fn test_agrees_with_truth_tables() {
+    let sentences = [
+      "p ^ ~p",
+      "p v ~p",
+      "(p -> q) ^ (q -> r) ^ p ^ ~r",
+      "(p <-> q) ^ (q <-> ~p)",
+      "~((p -> q) -> ((q -> r) -> (p -> r)))",
+      "(p v q) ^ (~p v r) ^ ~(q v r)",
+      "T ^ ~(F v p)",
+      "(p ^ q -> r) <-> (p -> (q -> r))",
+    ];
+    for s in sentences.iter() {
+      let e = parse(s);
+      let cnf = Cnf::tseitin(std::slice::from_ref(&e));
+      let satisfiable = !Expression::Negated(Box::new(e.clone())).is_tautology();
+      match cnf.solve() {
+        Some(solution) => {
+          assert!(satisfiable, "{}", s);
+          assert!(cnf.valuation(&solution).satisfies(&e), "{}", s);
+        }
+        None => assert!(!satisfiable, "{}", s),
+      }
+    }
+  }
+
+  #[test]
+  fn test_hundreds_of_letters() {
+    let chain = (1..300).map(|i| format!("(p{} -> p{})", i, i + 1)).collect::<Vec<_>>().join(" ^ ");
+    let e = parse(&format!("{} -> (p1 -> p300)", chain));
+    assert!(e.is_tautology());
+    let e = parse(&format!("{} -> (p300 -> p1)", chain));
+    assert!(!e.is_tautology());
+  }
+}
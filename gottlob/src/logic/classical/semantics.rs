@@ -0,0 +1,209 @@
+//! The usual semantic questions about classical sentences, each answered with the valuation that shows the answer is
+//! right whenever one exists.  They all come down to whether some set of sentences can be true together, which we
+//! find out with a truth table, or with the SAT solver once there are more than `SAT_THRESHOLD` sentence letters.
+//! Tableaux are for showing why an answer is right, not for finding it: they can grow exponentially even where a
+//! truth table stays small.
+
+use super::countermodel::Countermodel;
+use super::sat::Cnf;
+use super::{Expression, Variable};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// An assignment of truth values to sentence letters.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct Valuation(pub BTreeMap<Variable, bool>);
+
+impl Valuation {
+  pub fn trues(&self) -> HashSet<Variable> {
+    self.0.iter().filter(|(_, t)| **t).map(|(v, _)| *v).collect()
+  }
+
+  pub fn satisfies(&self, e: &Expression) -> bool {
+    e.eval(&self.trues())
+  }
+}
+
+impl From<Countermodel> for Valuation {
+  fn from(c: Countermodel) -> Self {
+    Self(c.valuation)
+  }
+}
+
+impl std::fmt::Display for Valuation {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", Countermodel { valuation: self.0.clone() })
+  }
+}
+
+/// A yes or no answer, with the valuations that show it.  What the witnesses show depends on the question.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Verdict {
+  pub holds: bool,
+  pub witnesses: Vec<Valuation>,
+}
+
+impl Verdict {
+  pub fn witness(&self) -> Option<&Valuation> {
+    self.witnesses.first()
+  }
+}
+
+/// Beyond this many sentence letters, truth tables get slow and we use the SAT solver instead.
+pub const SAT_THRESHOLD: usize = 12;
+
+/// A valuation making every one of `sentences` true, if there is one.
+fn model(sentences: &[Expression]) -> Option<Valuation> {
+  let letters = sentences.iter().flat_map(|s| s.variables()).collect::<BTreeSet<_>>();
+  let valuation = if letters.len() > SAT_THRESHOLD {
+    let cnf = Cnf::tseitin(sentences);
+    cnf.valuation(&cnf.solve()?)
+  } else {
+    // The first row of the truth table, in the same order as `TruthTable`, that makes them all true.
+    let row = |i: usize| {
+      let values = letters.iter().enumerate().map(|(j, v)| (*v, i & (1 << (letters.len() - 1 - j)) == 0));
+      Valuation(values.collect())
+    };
+    (0..1usize << letters.len()).map(row).find(|v| sentences.iter().all(|s| v.satisfies(s)))?
+  };
+  debug_assert!(sentences.iter().all(|s| valuation.satisfies(s)));
+  Some(valuation)
+}
+
+fn negated(e: &Expression) -> Expression {
+  Expression::Negated(Box::new(e.clone()))
+}
+
+impl Expression {
+  /// The witness is a valuation making this true.
+  pub fn is_satisfiable(&self) -> Verdict {
+    are_jointly_consistent(std::slice::from_ref(self))
+  }
+
+  /// When this isn't a contradiction, the witness is a valuation making it true.
+  pub fn is_contradiction(&self) -> Verdict {
+    let Verdict { holds, witnesses } = self.is_satisfiable();
+    Verdict { holds: !holds, witnesses }
+  }
+
+  /// The witnesses are a valuation making this true and one making it false, as far as they exist.
+  pub fn is_contingent(&self) -> Verdict {
+    let witnesses = vec![model(std::slice::from_ref(self)), model(&[negated(self)])];
+    Verdict {
+      holds: witnesses.iter().all(|w| w.is_some()),
+      witnesses: witnesses.into_iter().flatten().collect(),
+    }
+  }
+}
+
+/// When `a` and `b` aren't equivalent, the witness is a valuation on which they differ.
+pub fn are_equivalent(a: &Expression, b: &Expression) -> Verdict {
+  let differ = negated(&Expression::Biconditional(Box::new(a.clone()), Box::new(b.clone())));
+  let witnesses = model(&[differ]).into_iter().collect::<Vec<_>>();
+  Verdict {
+    holds: witnesses.is_empty(),
+    witnesses,
+  }
+}
+
+/// When the premises don't entail the conclusion, the witness is a countermodel.
+pub fn entails(premises: &[Expression], conclusion: &Expression) -> Verdict {
+  let mut sentences = premises.to_vec();
+  sentences.push(negated(conclusion));
+  let witnesses = model(&sentences).into_iter().collect::<Vec<_>>();
+  Verdict {
+    holds: witnesses.is_empty(),
+    witnesses,
+  }
+}
+
+/// The witness is a valuation making every sentence true.
+pub fn are_jointly_consistent(sentences: &[Expression]) -> Verdict {
+  let witnesses = model(sentences).into_iter().collect::<Vec<_>>();
+  Verdict {
+    holds: !witnesses.is_empty(),
+    witnesses,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+
+  fn parse(s: &str) -> Expression {
+    ClassicalParser::parse_expression(s).unwrap()
+  }
+
+  #[test]
+  fn test_classification() {
+    let p_and_not_p = parse("p ^ ~p");
+    assert!(!p_and_not_p.is_satisfiable().holds);
+    assert!(p_and_not_p.is_contradiction().holds);
+    assert_eq!(p_and_not_p.is_contradiction().witness(), None);
+    assert!(!p_and_not_p.is_contingent().holds);
+
+    let e = parse("p -> q ^ r");
+    let satisfiable = e.is_satisfiable();
+    assert!(satisfiable.holds);
+    assert!(satisfiable.witness().unwrap().satisfies(&e));
+    assert!(!e.is_contradiction().holds);
+    let contingent = e.is_contingent();
+    assert!(contingent.holds);
+    assert!(contingent.witnesses[0].satisfies(&e));
+    assert!(!contingent.witnesses[1].satisfies(&e));
+
+    let tautology = parse("p v ~p");
+    assert!(tautology.is_satisfiable().holds);
+    assert!(!tautology.is_contingent().holds);
+    assert_eq!(tautology.is_contingent().witnesses.len(), 1);
+    assert!(!parse("F").is_satisfiable().holds);
+  }
+
+  #[test]
+  fn test_equivalence_and_entailment() {
+    assert!(are_equivalent(&parse("~(p ^ q)"), &parse("~p v ~q")).holds);
+    let a = parse("p -> q");
+    let b = parse("q -> p");
+    let verdict = are_equivalent(&a, &b);
+    assert!(!verdict.holds);
+    let w = verdict.witness().unwrap();
+    assert_ne!(w.satisfies(&a), w.satisfies(&b));
+
+    assert!(entails(&[parse("p -> q"), parse("p")], &parse("q")).holds);
+    let verdict = entails(&[parse("p -> q"), parse("q")], &parse("p"));
+    assert!(!verdict.holds);
+    assert_eq!(verdict.witness().unwrap().to_string(), "v(p) = 0, v(q) = 1");
+  }
+
+  #[test]
+  fn test_joint_consistency() {
+    let sentences = [parse("p v q"), parse("~p"), parse("q -> r")];
+    let verdict = are_jointly_consistent(&sentences);
+    assert!(verdict.holds);
+    assert!(sentences.iter().all(|s| verdict.witness().unwrap().satisfies(s)));
+    assert!(!are_jointly_consistent(&[parse("p v q"), parse("~p"), parse("~q")]).holds);
+    assert!(are_jointly_consistent(&[]).holds);
+  }
+
+  #[test]
+  fn test_tseitin_output() {
+    // Eleven letters and 24 clauses: nothing for a truth table, but enough to keep a tableau branching for a minute.
+    let cnf = parse("((p -> q) ^ (r v s)) <-> ((p ^ r) v (q <-> ~s))").to_tseitin_cnf();
+    let verdict = cnf.is_satisfiable();
+    assert!(verdict.holds);
+    assert!(verdict.witness().unwrap().satisfies(&cnf));
+    assert!(!entails(&[], &negated(&cnf)).holds);
+  }
+
+  #[test]
+  fn test_many_letters() {
+    let premises = (1..100).map(|i| parse(&format!("p{} -> p{}", i, i + 1))).collect::<Vec<_>>();
+    assert!(entails(&premises, &parse("p1 -> p100")).holds);
+    let verdict = entails(&premises, &parse("p100 -> p1"));
+    assert!(!verdict.holds);
+    let w = verdict.witness().unwrap();
+    assert!(premises.iter().all(|p| w.satisfies(p)));
+    assert!(!w.satisfies(&parse("p100 -> p1")));
+    assert_eq!(w.0.len(), 100);
+  }
+}
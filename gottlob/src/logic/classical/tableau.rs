@@ -0,0 +1,289 @@
+//! Tableaux for classical propositional logic, following chapter 1 of Priest.
+//!
+//! To test whether some assumptions entail a conclusion, we list the assumptions and the negation of the conclusion and
+//! then decompose complex sentences until nothing is left to do.  If every branch closes, the inference is valid;
+//! otherwise any open branch describes a way to make the assumptions true and the conclusion false.
+//!
+//! These tableaux are only for showing the working.  `ClassicalLogic` decides inferences with `semantics::entails`
+//! instead, since a tableau can grow exponentially where a truth table or the SAT solver stays small.
+
+use super::countermodel::Countermodel;
+use super::{Expression, Theorem};
+use crate::logic::latex::{self, Latex};
+use crate::logic::tableau::{Branching, NodeId, Tree};
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Rule {
+  Assumption,
+  NegatedConclusion,
+  DoubleNegation,
+  Conjunction,
+  NegatedConjunction,
+  Disjunction,
+  NegatedDisjunction,
+  Conditional,
+  NegatedConditional,
+  Biconditional,
+  NegatedBiconditional,
+  Nand,
+  NegatedNand,
+  Nor,
+  NegatedNor,
+  Xor,
+  NegatedXor,
+}
+
+impl Branching for Rule {
+  fn is_branching(&self) -> bool {
+    matches!(
+      self,
+      Self::NegatedConjunction
+        | Self::Disjunction
+        | Self::Conditional
+        | Self::Biconditional
+        | Self::NegatedBiconditional
+        | Self::Nand
+        | Self::NegatedNor
+        | Self::Xor
+        | Self::NegatedXor
+    )
+  }
+}
+
+impl std::fmt::Display for Rule {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let s = match self {
+      Self::Assumption => "A",
+      Self::NegatedConclusion => "¬C",
+      Self::DoubleNegation => "¬¬",
+      Self::Conjunction => "∧",
+      Self::NegatedConjunction => "¬∧",
+      Self::Disjunction => "∨",
+      Self::NegatedDisjunction => "¬∨",
+      Self::Conditional => "→",
+      Self::NegatedConditional => "¬→",
+      Self::Biconditional => "↔",
+      Self::NegatedBiconditional => "¬↔",
+      Self::Nand => "↑",
+      Self::NegatedNand => "¬↑",
+      Self::Nor => "↓",
+      Self::NegatedNor => "¬↓",
+      Self::Xor => "⊕",
+      Self::NegatedXor => "¬⊕",
+    };
+    write!(f, "{}", s)
+  }
+}
+
+impl Latex for Rule {
+  fn latex(&self) -> String {
+    latex::from_unicode(&self.to_string())
+  }
+}
+
+/// What a rule does to a sentence: each inner `Vec` is one branch, listing the sentences added to it.
+fn decompose(e: &Expression) -> Option<(Rule, Vec<Vec<Expression>>)> {
+  use Expression::*;
+  let neg = |e: &Expression| Negated(Box::new(e.clone()));
+  let decomposition = match e {
+    Variable(_) | Top | Bottom => return None,
+    And(a, b) => (Rule::Conjunction, vec![vec![*a.clone(), *b.clone()]]),
+    Or(a, b) => (Rule::Disjunction, vec![vec![*a.clone()], vec![*b.clone()]]),
+    Conditional(a, b) => (Rule::Conditional, vec![vec![neg(a)], vec![*b.clone()]]),
+    Biconditional(a, b) => (
+      Rule::Biconditional,
+      vec![vec![*a.clone(), *b.clone()], vec![neg(a), neg(b)]],
+    ),
+    Nand(a, b) => (Rule::Nand, vec![vec![neg(a)], vec![neg(b)]]),
+    Nor(a, b) => (Rule::Nor, vec![vec![neg(a), neg(b)]]),
+    Xor(a, b) => (Rule::Xor, vec![vec![*a.clone(), neg(b)], vec![neg(a), *b.clone()]]),
+    Negated(inner) => match inner.as_ref() {
+      Variable(_) | Top | Bottom => return None,
+      Negated(a) => (Rule::DoubleNegation, vec![vec![*a.clone()]]),
+      And(a, b) => (Rule::NegatedConjunction, vec![vec![neg(a)], vec![neg(b)]]),
+      Or(a, b) => (Rule::NegatedDisjunction, vec![vec![neg(a), neg(b)]]),
+      Conditional(a, b) => (Rule::NegatedConditional, vec![vec![*a.clone(), neg(b)]]),
+      Biconditional(a, b) => (
+        Rule::NegatedBiconditional,
+        vec![vec![*a.clone(), neg(b)], vec![neg(a), *b.clone()]],
+      ),
+      Nand(a, b) => (Rule::NegatedNand, vec![vec![*a.clone(), *b.clone()]]),
+      Nor(a, b) => (Rule::NegatedNor, vec![vec![*a.clone()], vec![*b.clone()]]),
+      Xor(a, b) => (Rule::NegatedXor, vec![vec![*a.clone(), *b.clone()], vec![neg(a), neg(b)]]),
+    },
+  };
+  Some(decomposition)
+}
+
+#[derive(Debug, Clone)]
+pub struct Tableau {
+  tree: Tree<Expression, Rule>,
+  complete: bool,
+}
+
+impl Tableau {
+  /// Builds and completes the tableau for the inference from `assumptions` to `conclusion`.
+  pub fn new(assumptions: &[Expression], conclusion: &Expression) -> Self {
+    let mut tableau = Self {
+      tree: Tree::new(),
+      complete: false,
+    };
+    let mut leaf = None;
+    for a in assumptions {
+      leaf = Some(tableau.tree.push(leaf, a.clone(), Rule::Assumption, None));
+    }
+    let negated_conclusion = Expression::Negated(Box::new(conclusion.clone()));
+    let leaf = tableau.tree.push(leaf, negated_conclusion, Rule::NegatedConclusion, None);
+    if is_contradictory(&tableau.tree, leaf) {
+      tableau.tree.close(leaf);
+    }
+    tableau.complete = tableau.tree.decompose_all(decompose, is_contradictory);
+    tableau
+  }
+
+  pub fn for_theorem(thm: &Theorem) -> Self {
+    Self::new(thm.assumptions(), thm.conclusion())
+  }
+
+  pub fn tree(&self) -> &Tree<Expression, Rule> {
+    &self.tree
+  }
+
+  /// A closed tableau means the inference is valid.
+  pub fn is_closed(&self) -> bool {
+    self.tree.is_closed()
+  }
+
+  /// False if we gave up because the tableau grew too large, in which case an open branch proves nothing.
+  pub fn is_complete(&self) -> bool {
+    self.complete
+  }
+
+  /// The sentences on each completed open branch.
+  pub fn open_branches(&self) -> Vec<Vec<&Expression>> {
+    self.tree.open_leaves().into_iter().map(|leaf| self.tree.branch_contents(leaf)).collect()
+  }
+
+  /// The interpretation described by the first open branch, if there is one.
+  pub fn countermodel(&self) -> Option<Countermodel> {
+    if !self.complete {
+      return None;
+    }
+    let leaf = *self.tree.open_leaves().first()?;
+    let variables = self.tree.nodes().flat_map(|(_, n)| n.content.variables()).collect();
+    Some(Countermodel::from_branch(self.tree.branch_contents(leaf), variables))
+  }
+}
+
+fn is_contradictory(tree: &Tree<Expression, Rule>, leaf: NodeId) -> bool {
+  let branch = tree.branch_contents(leaf);
+  // Nothing makes `⊥` or `¬⊤` true, so either closes a branch on its own.
+  branch.iter().any(|e| match e {
+    Expression::Bottom => true,
+    Expression::Negated(inner) => **inner == Expression::Top || branch.iter().any(|other| *other == inner.as_ref()),
+    _ => false,
+  })
+}
+
+impl std::fmt::Display for Tableau {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.tree)
+  }
+}
+
+impl Latex for Tableau {
+  fn latex(&self) -> String {
+    self.tree.latex()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+
+  fn tableau(assumptions: &[&str], conclusion: &str) -> Tableau {
+    let assumptions = assumptions
+      .iter()
+      .map(|a| ClassicalParser::parse_expression(a).unwrap())
+      .collect::<Vec<_>>();
+    Tableau::new(&assumptions, &ClassicalParser::parse_expression(conclusion).unwrap())
+  }
+
+  #[test]
+  fn test_valid_inferences_close() {
+    assert!(tableau(&[], "p v ~p").is_closed());
+    assert!(tableau(&[], "~(p ^ q) <-> ~p v ~q").is_closed());
+    assert!(tableau(&["p -> q", "p"], "q").is_closed());
+    assert!(tableau(&["p -> q", "~q"], "~p").is_closed());
+    assert!(tableau(&["p v q", "~p"], "q").is_closed());
+    assert!(tableau(&["p <-> q", "q <-> r"], "p <-> r").is_closed());
+    assert!(tableau(&[], "T").is_closed());
+    assert!(tableau(&["F"], "p").is_closed());
+    assert!(tableau(&["p -> F"], "~p").is_closed());
+    assert!(tableau(&[], "(p nand q) <-> ~(p ^ q)").is_closed());
+    assert!(tableau(&[], "(p nor q) <-> ~p ^ ~q").is_closed());
+    assert!(tableau(&[], "(p xor q) <-> ~(p <-> q)").is_closed());
+    assert!(tableau(&["p nand p"], "~p").is_closed());
+  }
+
+  #[test]
+  fn test_invalid_inferences_stay_open() {
+    assert!(!tableau(&[], "p").is_closed());
+    assert!(!tableau(&["p -> q", "q"], "p").is_closed());
+    assert!(!tableau(&["p v q"], "p ^ q").is_closed());
+    assert!(!tableau(&[], "F").is_closed());
+    assert!(!tableau(&["T"], "p").is_closed());
+    assert!(!tableau(&["p xor q"], "p").is_closed());
+    assert!(!tableau(&["p nor q"], "p nand q ^ p").is_closed());
+  }
+
+  #[test]
+  fn test_tree_structure() {
+    let t = tableau(&["p ^ q"], "p");
+    let tree = t.tree();
+    assert_eq!(tree.node(0).rule, Rule::Assumption);
+    assert_eq!(tree.node(1).rule, Rule::NegatedConclusion);
+    assert_eq!(tree.node(2).rule, Rule::Conjunction);
+    assert_eq!(tree.node(2).source, Some(0));
+    assert_eq!(tree.leaves().len(), 1);
+    assert!(t.is_closed());
+
+    let t = tableau(&["p v q"], "p");
+    assert_eq!(t.tree().leaves().len(), 2);
+    assert_eq!(t.open_branches().len(), 1);
+  }
+
+  #[test]
+  fn test_gives_up_on_huge_tableaux() {
+    let premises = (1..=13).map(|i| format!("a{} v b{}", i, i)).collect::<Vec<_>>();
+    let premises = premises.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+    let t = tableau(&premises, "(x ^ y) v ~x v ~y");
+    assert!(!t.is_complete());
+    assert_eq!(t.countermodel(), None);
+    assert!(tableau(&premises[..3], "x").is_complete());
+  }
+
+  #[test]
+  fn test_countermodels() {
+    let cases: &[(&[&str], &str)] = &[
+      (&[], "p"),
+      (&["p -> q", "q"], "p"),
+      (&["p v q"], "p ^ q"),
+      (&["p <-> q", "r"], "~(p ^ r) -> q"),
+      (&["p v F"], "~T v ~p"),
+    ];
+    for (assumptions, conclusion) in cases {
+      let t = tableau(assumptions, conclusion);
+      let countermodel = t.countermodel().expect("invalid inferences have countermodels");
+      let assumptions = assumptions
+        .iter()
+        .map(|a| ClassicalParser::parse_expression(a).unwrap())
+        .collect::<Vec<_>>();
+      let conclusion = ClassicalParser::parse_expression(conclusion).unwrap();
+      assert!(countermodel.is_countermodel(&assumptions, &conclusion), "{}", countermodel);
+    }
+    assert_eq!(tableau(&["p"], "p").countermodel(), None);
+  }
+
+}
@@ -0,0 +1,270 @@
+//! Truth tables, with a column for every sentence letter and every compound subformula.
+
+use super::{Expression, Variable};
+use crate::logic::formula::{Formula, Shape};
+use std::collections::{BTreeMap, HashSet};
+
+/// One row of a truth table: a valuation of the sentence letters and the value it gives each compound column.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Row {
+  pub valuation: BTreeMap<Variable, bool>,
+  pub values: Vec<bool>,
+}
+
+impl Row {
+  pub fn trues(&self) -> HashSet<Variable> {
+    self.valuation.iter().filter(|(_, t)| **t).map(|(v, _)| *v).collect()
+  }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub enum TableFormat {
+  Text,
+  Markdown,
+  Html,
+  Csv,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TruthTable {
+  variables: Vec<Variable>,
+  /// Compound subformulas, each after its own subformulas, ending with the sentences the table was built for.
+  columns: Vec<Expression>,
+  assumptions: Vec<Expression>,
+  conclusion: Option<Expression>,
+  rows: Vec<Row>,
+}
+
+impl TruthTable {
+  /// A table for any number of sentences.  Letters are in alphabetical order and rows start from all true, as in most
+  /// textbooks.
+  pub fn new(sentences: &[Expression]) -> Self {
+    let mut variables = sentences.iter().flat_map(|e| e.variables()).collect::<Vec<_>>();
+    variables.sort();
+    variables.dedup();
+    let mut columns = Vec::new();
+    for e in sentences {
+      add_columns(e, &mut columns);
+    }
+    let rows = (0..1usize << variables.len())
+      .map(|i| {
+        let valuation = variables
+          .iter()
+          .enumerate()
+          .map(|(j, v)| (*v, i & (1 << (variables.len() - 1 - j)) == 0))
+          .collect::<BTreeMap<_, _>>();
+        let trues = valuation.iter().filter(|(_, t)| **t).map(|(v, _)| *v).collect();
+        let values = columns.iter().map(|c: &Expression| c.eval(&trues)).collect();
+        Row { valuation, values }
+      })
+      .collect();
+    Self {
+      variables,
+      columns,
+      assumptions: Vec::new(),
+      conclusion: None,
+      rows,
+    }
+  }
+
+  /// A table for an argument, which knows which columns are the assumptions and which the conclusion.
+  pub fn for_argument(assumptions: &[Expression], conclusion: &Expression) -> Self {
+    let mut sentences = assumptions.to_vec();
+    sentences.push(conclusion.clone());
+    Self {
+      assumptions: assumptions.to_vec(),
+      conclusion: Some(conclusion.clone()),
+      ..Self::new(&sentences)
+    }
+  }
+
+  pub fn variables(&self) -> &[Variable] {
+    &self.variables
+  }
+
+  pub fn columns(&self) -> &[Expression] {
+    &self.columns
+  }
+
+  pub fn rows(&self) -> &[Row] {
+    &self.rows
+  }
+
+  /// The value of `e` in `row`, if `e` is one of the table's letters or columns.
+  pub fn value(&self, row: &Row, e: &Expression) -> Option<bool> {
+    match e {
+      Expression::Variable(v) => row.valuation.get(v).cloned(),
+      Expression::Top => Some(true),
+      Expression::Bottom => Some(false),
+      e => self.columns.iter().position(|c| c == e).map(|i| row.values[i]),
+    }
+  }
+
+  /// The rows in which `e` is true.
+  pub fn rows_where(&self, e: &Expression) -> Vec<&Row> {
+    self.rows.iter().filter(|r| self.value(r, e) == Some(true)).collect()
+  }
+
+  /// For a table built with `for_argument`, the rows where every assumption is true and the conclusion false; the
+  /// argument is valid when there are none.
+  pub fn counterexamples(&self) -> Vec<&Row> {
+    let conclusion = match &self.conclusion {
+      Some(c) => c,
+      None => return Vec::new(),
+    };
+    self
+      .rows
+      .iter()
+      .filter(|r| {
+        self.assumptions.iter().all(|a| self.value(r, a) == Some(true)) && self.value(r, conclusion) == Some(false)
+      })
+      .collect()
+  }
+
+  pub fn render(&self, format: TableFormat) -> String {
+    let header = self
+      .variables
+      .iter()
+      .map(|v| v.to_string())
+      .chain(self.columns.iter().map(|c| c.to_string()))
+      .collect::<Vec<_>>();
+    let cells = self
+      .rows
+      .iter()
+      .map(|r| {
+        r.valuation
+          .values()
+          .chain(r.values.iter())
+          .map(|t| if *t { "1" } else { "0" }.to_owned())
+          .collect::<Vec<_>>()
+      })
+      .collect::<Vec<_>>();
+    render_grid(&header, &cells, format)
+  }
+}
+
+/// Lays out a header and rows of cells in one of the table formats.
+pub(super) fn render_grid(header: &[String], cells: &[Vec<String>], format: TableFormat) -> String {
+  match format {
+    TableFormat::Text => {
+      let widths = (0..header.len())
+        .map(|i| cells.iter().map(|r| &r[i]).chain(Some(&header[i])).map(|c| c.chars().count()).max().unwrap())
+        .collect::<Vec<_>>();
+      let line = |cells: &[String]| {
+        let padded = cells
+          .iter()
+          .zip(&widths)
+          .map(|(c, w)| format!("{}{}", c, " ".repeat(w - c.chars().count())))
+          .collect::<Vec<_>>();
+        padded.join(" | ").trim_end().to_owned()
+      };
+      let rule = widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>().join("-+-");
+      let mut lines = vec![line(header), rule];
+      lines.extend(cells.iter().map(|r| line(r)));
+      lines.join("\n")
+    }
+    TableFormat::Markdown => {
+      let line = |cells: &[String]| format!("| {} |", cells.join(" | "));
+      let mut lines = vec![line(header), format!("|{}", "---|".repeat(header.len()))];
+      lines.extend(cells.iter().map(|r| line(r)));
+      lines.join("\n")
+    }
+    TableFormat::Html => {
+      let line = |cells: &[String], tag: &str| {
+        let cells = cells
+          .iter()
+          .map(|c| format!("<{}>{}</{}>", tag, escape_html(c), tag))
+          .collect::<String>();
+        format!("  <tr>{}</tr>", cells)
+      };
+      let mut lines = vec!["<table>".to_owned(), line(header, "th")];
+      lines.extend(cells.iter().map(|r| line(r, "td")));
+      lines.push("</table>".to_owned());
+      lines.join("\n")
+    }
+    TableFormat::Csv => {
+      let line = |cells: &[String]| cells.iter().map(|c| escape_csv(c)).collect::<Vec<_>>().join(",");
+      let mut lines = vec![line(header)];
+      lines.extend(cells.iter().map(|r| line(r)));
+      lines.join("\n")
+    }
+  }
+}
+
+fn add_columns(e: &Expression, columns: &mut Vec<Expression>) {
+  match e.shape() {
+    Shape::Variable(_) | Shape::Constant(_) => return,
+    Shape::Unary(_, a) => add_columns(a, columns),
+    Shape::Binary(_, a, b) => {
+      add_columns(a, columns);
+      add_columns(b, columns);
+    }
+  }
+  if !columns.contains(e) {
+    columns.push(e.clone());
+  }
+}
+
+fn escape_html(s: &str) -> String {
+  s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn escape_csv(s: &str) -> String {
+  if s.contains(',') || s.contains('"') || s.contains('\n') {
+    format!("\"{}\"", s.replace('"', "\"\""))
+  } else {
+    s.to_owned()
+  }
+}
+
+impl std::fmt::Display for TruthTable {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.render(TableFormat::Text))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+
+  fn parse(s: &str) -> Expression {
+    ClassicalParser::parse_expression(s).unwrap()
+  }
+
+  #[test]
+  fn test_columns_and_rows() {
+    let t = TruthTable::new(&[parse("~(q ^ p) v q ^ p")]);
+    assert_eq!(t.variables(), &[Variable::new("p"), Variable::new("q")]);
+    let columns = t.columns().iter().map(|c| c.to_string()).collect::<Vec<_>>();
+    assert_eq!(columns, vec!["q ∧ p", "¬(q ∧ p)", "¬(q ∧ p) ∨ q ∧ p"]);
+    assert_eq!(t.rows().len(), 4);
+    assert_eq!(t.rows()[0].valuation.values().collect::<Vec<_>>(), vec![&true, &true]);
+    assert_eq!(t.rows()[1].valuation.values().collect::<Vec<_>>(), vec![&true, &false]);
+    assert!(t.rows().iter().all(|r| *r.values.last().unwrap()));
+    assert_eq!(t.rows_where(&parse("q ^ p")).len(), 1);
+    assert_eq!(t.rows_where(&parse("p")).len(), 2);
+  }
+
+  #[test]
+  fn test_counterexamples() {
+    let t = TruthTable::for_argument(&[parse("p -> q"), parse("q")], &parse("p"));
+    let counterexamples = t.counterexamples();
+    assert_eq!(counterexamples.len(), 1);
+    assert_eq!(counterexamples[0].trues(), [Variable::new("q")].iter().cloned().collect());
+    assert!(TruthTable::for_argument(&[parse("p -> q"), parse("p")], &parse("q")).counterexamples().is_empty());
+    assert!(TruthTable::new(&[parse("p")]).counterexamples().is_empty());
+  }
+
+  #[test]
+  fn test_render() {
+    let t = TruthTable::new(&[parse("p -> T")]);
+    assert_eq!(t.to_string(), "p | p → ⊤\n--+------\n1 | 1\n0 | 1");
+    assert_eq!(t.render(TableFormat::Markdown), "| p | p → ⊤ |\n|---|---|\n| 1 | 1 |\n| 0 | 1 |");
+    assert_eq!(
+      t.render(TableFormat::Html),
+      "<table>\n  <tr><th>p</th><th>p → ⊤</th></tr>\n  <tr><td>1</td><td>1</td></tr>\n  <tr><td>0</td><td>1</td></tr>\n</table>"
+    );
+    assert_eq!(t.render(TableFormat::Csv), "p,p → ⊤\n1,1\n0,1");
+  }
+}
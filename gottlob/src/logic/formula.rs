@@ -0,0 +1,451 @@
+//! What the formula languages of our logics have in common.
+//!
+//! Each logic keeps its own `Expression` type so that it can only represent sentences of its own language, but every
+//! `Expression` implements `Formula`, which exposes it one operator at a time.  That is enough to share a parser, a
+//! printer and conversions between languages without each logic having to copy them.
+
+use lazy_static::*;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::RwLock;
+
+lazy_static! {
+  static ref VARIABLE_NAMES: RwLock<Interner> = RwLock::new(Interner::default());
+}
+
+#[derive(Default)]
+struct Interner {
+  names: Vec<String>,
+  indices: HashMap<String, usize>,
+}
+
+/// A sentence letter such as `p`, `q_12` or `rain`.  Names are interned, so variables are cheap to copy and compare.
+#[derive(Eq, PartialEq, Clone, Copy, Hash)]
+pub struct Variable(usize);
+
+impl Variable {
+  pub fn new(name: &str) -> Self {
+    if let Some(i) = VARIABLE_NAMES.read().unwrap().indices.get(name) {
+      return Variable(*i);
+    }
+    let mut interner = VARIABLE_NAMES.write().unwrap();
+    if let Some(i) = interner.indices.get(name) {
+      return Variable(*i);
+    }
+    let i = interner.names.len();
+    interner.names.push(name.to_owned());
+    interner.indices.insert(name.to_owned(), i);
+    Variable(i)
+  }
+
+  pub fn name(&self) -> String {
+    VARIABLE_NAMES.read().unwrap().names[self.0].clone()
+  }
+}
+
+impl From<&str> for Variable {
+  fn from(name: &str) -> Self {
+    Self::new(name)
+  }
+}
+
+/// Variables are ordered by name rather than by when they were first seen, so that anything listing them is
+/// deterministic.
+impl Ord for Variable {
+  fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    if self == other {
+      return std::cmp::Ordering::Equal;
+    }
+    let interner = VARIABLE_NAMES.read().unwrap();
+    interner.names[self.0].cmp(&interner.names[other.0])
+  }
+}
+
+impl PartialOrd for Variable {
+  fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl std::fmt::Debug for Variable {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "Variable({:?})", self.name())
+  }
+}
+
+impl std::fmt::Display for Variable {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+/// Whose modal operator an indexed box or diamond is, such as the `a` of `K_a` or `[a]`.  Named like sentence letters,
+/// though digits on their own are allowed too, as in `K_1`.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub struct Agent(Variable);
+
+impl Agent {
+  pub fn new(name: &str) -> Self {
+    Agent(Variable::new(name))
+  }
+
+  pub fn name(&self) -> String {
+    self.0.name()
+  }
+}
+
+impl std::fmt::Debug for Agent {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "Agent({:?})", self.name())
+  }
+}
+
+impl std::fmt::Display for Agent {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+/// Which accessibility relation an indexed box or diamond looks along.  Each index has its own relation, so `K_a`,
+/// `B_a` and `[a]` are about three different relations.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub enum Index {
+  /// `[a]` and `<a>`, for multi-modal logics.
+  Named(Agent),
+  /// `K_a`: what `a` knows.
+  Knowledge(Agent),
+  /// `B_a`: what `a` believes.
+  Belief(Agent),
+}
+
+impl Index {
+  pub fn agent(&self) -> Agent {
+    match self {
+      Self::Named(a) | Self::Knowledge(a) | Self::Belief(a) => *a,
+    }
+  }
+}
+
+impl std::fmt::Display for Index {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Named(a) => write!(f, "{}", a),
+      Self::Knowledge(a) => write!(f, "K_{}", a),
+      Self::Belief(a) => write!(f, "B_{}", a),
+    }
+  }
+}
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub enum Operator {
+  Not,
+  And,
+  Or,
+  Conditional,
+  Biconditional,
+  Nand,
+  Nor,
+  Xor,
+  Necessary,
+  Possible,
+  /// `G`: it will always be that.
+  FutureAlways,
+  /// `F`: it will at some time be that.
+  FutureSometime,
+  /// `H`: it has always been that.
+  PastAlways,
+  /// `P`: it was at some time that.
+  PastSometime,
+  /// `[a]`, `K_a` or `B_a`: true at every world the index's relation reaches.
+  IndexedNecessary(Index),
+  /// `<a>`, `<K_a>` or `<B_a>`: true at some world the index's relation reaches.
+  IndexedPossible(Index),
+  /// `E`: every agent knows that.
+  EverybodyKnows,
+  /// `C`: it is common knowledge that, so everybody knows it, everybody knows everybody knows it, and so on.
+  CommonKnowledge,
+}
+
+impl Operator {
+  pub fn is_unary(&self) -> bool {
+    matches!(
+      self,
+      Self::Not
+        | Self::Necessary
+        | Self::Possible
+        | Self::FutureAlways
+        | Self::FutureSometime
+        | Self::PastAlways
+        | Self::PastSometime
+        | Self::IndexedNecessary(_)
+        | Self::IndexedPossible(_)
+        | Self::EverybodyKnows
+        | Self::CommonKnowledge
+    )
+  }
+
+  /// The operator that means the same as this one with a negation on either side, as `◇` is `¬◻¬`.
+  pub fn dual(&self) -> Option<Self> {
+    match self {
+      Self::Necessary => Some(Self::Possible),
+      Self::Possible => Some(Self::Necessary),
+      Self::FutureAlways => Some(Self::FutureSometime),
+      Self::FutureSometime => Some(Self::FutureAlways),
+      Self::PastAlways => Some(Self::PastSometime),
+      Self::PastSometime => Some(Self::PastAlways),
+      Self::IndexedNecessary(i) => Some(Self::IndexedPossible(*i)),
+      Self::IndexedPossible(i) => Some(Self::IndexedNecessary(*i)),
+      _ => None,
+    }
+  }
+
+  /// How `Display` writes the operator.  Indexed operators are written with their index, as `K_a` or `⟨a⟩`.
+  pub fn symbol(&self) -> String {
+    let symbol = match self {
+      Self::Not => "¬",
+      Self::And => "∧",
+      Self::Or => "∨",
+      Self::Conditional => "→",
+      Self::Biconditional => "↔",
+      Self::Nand => "↑",
+      Self::Nor => "↓",
+      Self::Xor => "⊕",
+      Self::Necessary => "◻",
+      Self::Possible => "◇",
+      Self::FutureAlways => "G",
+      Self::FutureSometime => "F",
+      Self::PastAlways => "H",
+      Self::PastSometime => "P",
+      Self::IndexedNecessary(Index::Named(a)) => return format!("[{}]", a),
+      Self::IndexedNecessary(i) => return i.to_string(),
+      Self::IndexedPossible(i) => return format!("⟨{}⟩", i),
+      Self::EverybodyKnows => "E",
+      Self::CommonKnowledge => "C",
+    };
+    symbol.to_owned()
+  }
+}
+
+impl std::fmt::Display for Operator {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.symbol())
+  }
+}
+
+/// The top level of a formula: a sentence letter, a truth constant (`⊤` is `Constant(true)`), or an operator and its
+/// operands.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Shape<T> {
+  Variable(Variable),
+  Constant(bool),
+  Unary(Operator, T),
+  Binary(Operator, T, T),
+}
+
+impl<T> Shape<T> {
+  pub fn operator(&self) -> Option<Operator> {
+    match self {
+      Self::Variable(_) | Self::Constant(_) => None,
+      Self::Unary(op, _) | Self::Binary(op, _, _) => Some(*op),
+    }
+  }
+}
+
+/// The formula languages we know about, each given by the operators it allows.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub enum Language {
+  Classical,
+  Modal,
+}
+
+impl Language {
+  /// Smallest first, so that the first language containing a formula is the most specific one.
+  pub const ALL: [Self; 2] = [Self::Classical, Self::Modal];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::Classical => "classical logic",
+      Self::Modal => "modal logic",
+    }
+  }
+
+  pub fn operators(&self) -> &'static [Operator] {
+    use Operator::*;
+    match self {
+      Self::Classical => &[Not, And, Or, Conditional, Biconditional, Nand, Nor, Xor],
+      Self::Modal => &[
+        Not,
+        And,
+        Or,
+        Conditional,
+        Biconditional,
+        Nand,
+        Nor,
+        Xor,
+        Necessary,
+        Possible,
+        FutureAlways,
+        FutureSometime,
+        PastAlways,
+        PastSometime,
+        EverybodyKnows,
+        CommonKnowledge,
+      ],
+    }
+  }
+
+  /// Whether the language has `op`.  Indexed operators can't be listed in `operators`, since there is one for each
+  /// index, so modal logic allows all of them besides.
+  pub fn allows(&self, op: Operator) -> bool {
+    match op {
+      Operator::IndexedNecessary(_) | Operator::IndexedPossible(_) => *self == Self::Modal,
+      op => self.operators().contains(&op),
+    }
+  }
+
+  pub fn contains<F: Formula>(&self, f: &F) -> bool {
+    f.operators().iter().all(|op| self.allows(*op))
+  }
+}
+
+pub trait Formula: Sized {
+  /// The language this type of formula represents.
+  fn language() -> Language;
+
+  fn shape(&self) -> Shape<&Self>;
+
+  /// Builds a formula from its top level.  Only ever called with operators `Self::language()` allows.
+  fn from_shape(shape: Shape<Self>) -> Self;
+
+  /// Like `from_shape`, but gives back the operator if the language doesn't have it.
+  fn try_from_shape(shape: Shape<Self>) -> Result<Self, Operator> {
+    match shape.operator() {
+      Some(op) if !Self::language().allows(op) => Err(op),
+      _ => Ok(Self::from_shape(shape)),
+    }
+  }
+
+  /// Every operator occurring anywhere in the formula.
+  fn operators(&self) -> BTreeSet<Operator> {
+    let mut operators = BTreeSet::new();
+    let mut stack = vec![self];
+    while let Some(f) = stack.pop() {
+      match f.shape() {
+        Shape::Variable(_) | Shape::Constant(_) => {}
+        Shape::Unary(op, a) => {
+          operators.insert(op);
+          stack.push(a);
+        }
+        Shape::Binary(op, a, b) => {
+          operators.insert(op);
+          stack.push(a);
+          stack.push(b);
+        }
+      }
+    }
+    operators
+  }
+
+  /// The fragment check: every language this formula could be written in, smallest first.
+  fn languages(&self) -> Vec<Language> {
+    Language::ALL.iter().cloned().filter(|l| l.contains(self)).collect()
+  }
+
+  /// Rewrites this formula in another language, failing with the first operator that language doesn't have.
+  fn convert<G: Formula>(&self) -> Result<G, Operator> {
+    let shape = match self.shape() {
+      Shape::Variable(v) => Shape::Variable(v),
+      Shape::Constant(c) => Shape::Constant(c),
+      Shape::Unary(op, a) => Shape::Unary(op, a.convert()?),
+      Shape::Binary(op, a, b) => Shape::Binary(op, a.convert()?, b.convert()?),
+    };
+    G::try_from_shape(shape)
+  }
+}
+
+/// An argument together with a claim that it is valid (`⊢`) or invalid (`⊬`).
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Theorem<F> {
+  Proves { assumptions: Vec<F>, conclusion: F },
+  DoesNotProve { assumptions: Vec<F>, conclusion: F },
+}
+
+impl<F> Theorem<F> {
+  pub fn new(assumptions: Vec<F>, proves: bool, conclusion: F) -> Self {
+    if proves {
+      Self::Proves { assumptions, conclusion }
+    } else {
+      Self::DoesNotProve { assumptions, conclusion }
+    }
+  }
+
+  pub fn assumptions(&self) -> &[F] {
+    match self {
+      Self::Proves { assumptions, .. } | Self::DoesNotProve { assumptions, .. } => assumptions,
+    }
+  }
+
+  pub fn conclusion(&self) -> &F {
+    match self {
+      Self::Proves { conclusion, .. } | Self::DoesNotProve { conclusion, .. } => conclusion,
+    }
+  }
+
+  /// Whether the claim is right, given whether the argument is in fact valid.
+  pub fn holds(&self, valid: bool) -> bool {
+    match self {
+      Self::Proves { .. } => valid,
+      Self::DoesNotProve { .. } => !valid,
+    }
+  }
+}
+
+impl<F: std::fmt::Display> std::fmt::Display for Theorem<F> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let op = match self {
+      Self::Proves { .. } => "⊢",
+      Self::DoesNotProve { .. } => "⊬",
+    };
+    let assumptions = self.assumptions().iter().map(|e| format!("{}", e)).collect::<Vec<_>>().join(", ");
+    write!(f, "{} {} {}", assumptions, op, self.conclusion())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::{classical, modal};
+  use std::convert::TryFrom;
+
+  #[test]
+  fn test_languages() {
+    let e = modal::parser::ModalParser::parse_expression("p -> q").unwrap();
+    assert_eq!(e.languages(), vec![Language::Classical, Language::Modal]);
+    let e = modal::parser::ModalParser::parse_expression("p -> <>q").unwrap();
+    assert_eq!(e.languages(), vec![Language::Modal]);
+    assert_eq!(e.operators().into_iter().collect::<Vec<_>>(), vec![Operator::Conditional, Operator::Possible]);
+    let e = modal::parser::ModalParser::parse_expression("K_a p ^ <b>q").unwrap();
+    assert_eq!(e.languages(), vec![Language::Modal]);
+    assert!(e.operators().contains(&Operator::IndexedNecessary(Index::Knowledge(Agent::new("a")))));
+  }
+
+  #[test]
+  fn test_variables() {
+    assert_eq!(Variable::new("p1"), Variable::from("p1"));
+    assert_ne!(Variable::new("p1"), Variable::new("p12"));
+    assert_eq!(Variable::new("rain").name(), "rain");
+    let mut vs = [Variable::new("zebra"), Variable::new("q_12"), Variable::new("alpha")];
+    vs.sort();
+    assert_eq!(vs.iter().map(|v| v.to_string()).collect::<Vec<_>>(), vec!["alpha", "q_12", "zebra"]);
+  }
+
+  #[test]
+  fn test_conversions() {
+    let c = classical::parser::ClassicalParser::parse_expression("~(p ^ q) <-> ~p v ~q").unwrap();
+    let m = modal::Expression::from(c.clone());
+    assert_eq!(format!("{}", c), format!("{}", m));
+    assert_eq!(classical::Expression::try_from(m), Ok(c));
+
+    let m = modal::parser::ModalParser::parse_expression("p ^ []q").unwrap();
+    assert_eq!(classical::Expression::try_from(m), Err(Operator::Necessary));
+  }
+}
@@ -0,0 +1,24 @@
+use super::kripke::KripkeModel;
+use crate::logic::classical::Expression;
+
+/// An intuitionistic Kripke interpretation in which the assumptions of an inference are established at stage 0, the
+/// earliest, and its conclusion isn't.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct Countermodel {
+  pub model: KripkeModel,
+}
+
+impl Countermodel {
+  /// Checks that this really is a countermodel by evaluating the inference at stage 0.
+  pub fn is_countermodel(&self, assumptions: &[Expression], conclusion: &Expression) -> bool {
+    self.model.is_persistent()
+      && assumptions.iter().all(|a| self.model.forces(0, a))
+      && !self.model.forces(0, conclusion)
+  }
+}
+
+impl std::fmt::Display for Countermodel {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.model)
+  }
+}
@@ -0,0 +1,164 @@
+use super::Variable;
+use crate::logic::classical::Expression;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub type Stage = usize;
+
+/// An intuitionistic Kripke interpretation: stages of knowledge, partially ordered by which come later than which, and
+/// the sentence letters established at each stage.  Letters not listed for a stage are not (yet) established there.
+///
+/// The order is kept reflexive and transitive, and what is established at a stage should stay established at every
+/// later one; `is_persistent` checks that.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct KripkeModel {
+  pub stages: BTreeSet<Stage>,
+  /// Pairs `(w, v)` with `w ≤ v`.
+  pub order: BTreeSet<(Stage, Stage)>,
+  pub valuation: BTreeMap<Stage, BTreeSet<Variable>>,
+}
+
+impl KripkeModel {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn add_stage(&mut self, w: Stage) {
+    self.stages.insert(w);
+    self.order.insert((w, w));
+  }
+
+  /// Makes `v` come after `w` and everything before `w`, and everything after `v` come after them too.
+  pub fn add_order(&mut self, w: Stage, v: Stage) {
+    self.add_stage(w);
+    self.add_stage(v);
+    let before = self.order.iter().filter(|(_, x)| *x == w).map(|(u, _)| *u).collect::<Vec<_>>();
+    let after = self.order.iter().filter(|(x, _)| *x == v).map(|(_, u)| *u).collect::<Vec<_>>();
+    for u in &before {
+      for x in &after {
+        self.order.insert((*u, *x));
+      }
+    }
+  }
+
+  pub fn set_true(&mut self, w: Stage, v: Variable) {
+    self.add_stage(w);
+    self.valuation.entry(w).or_default().insert(v);
+  }
+
+  pub fn is_true(&self, w: Stage, v: Variable) -> bool {
+    self.valuation.get(&w).is_some_and(|trues| trues.contains(&v))
+  }
+
+  /// The stages at or after `w`.
+  pub fn later(&self, w: Stage) -> impl Iterator<Item = Stage> + '_ {
+    self.order.range((w, Stage::MIN)..=(w, Stage::MAX)).map(|(_, v)| *v)
+  }
+
+  /// Whether every letter established at a stage is established at every later one.
+  pub fn is_persistent(&self) -> bool {
+    let established = |w: &Stage| self.valuation.get(w).into_iter().flatten();
+    self.order.iter().all(|(w, v)| established(w).all(|p| self.is_true(*v, *p)))
+  }
+
+  /// Whether `e` is established at `w`.
+  pub fn forces(&self, w: Stage, e: &Expression) -> bool {
+    self.forcing(e).contains(&w)
+  }
+
+  /// The stages at which `e` is established.  A negation or conditional needs to hold at every later stage too; the
+  /// other connectives are read as their definitions, with `A ↔ B` as `(A → B) ∧ (B → A)`, `A ↑ B` as `¬(A ∧ B)`,
+  /// `A ↓ B` as `¬(A ∨ B)` and `A ⊕ B` as `¬(A ↔ B)`.  Each subformula is only looked at once, however deeply the
+  /// negations and conditionals are nested.
+  pub fn forcing(&self, e: &Expression) -> BTreeSet<Stage> {
+    use Expression::*;
+    let everywhere_later = |holds: &dyn Fn(Stage) -> bool| {
+      self.stages.iter().filter(|w| self.later(**w).all(holds)).cloned().collect()
+    };
+    match e {
+      Variable(v) => self.stages.iter().filter(|w| self.is_true(**w, *v)).cloned().collect(),
+      Top => self.stages.clone(),
+      Bottom => BTreeSet::new(),
+      Negated(a) => {
+        let a = self.forcing(a);
+        everywhere_later(&|v| !a.contains(&v))
+      }
+      And(a, b) => self.forcing(a).intersection(&self.forcing(b)).cloned().collect(),
+      Or(a, b) => self.forcing(a).union(&self.forcing(b)).cloned().collect(),
+      Conditional(a, b) => {
+        let (a, b) = (self.forcing(a), self.forcing(b));
+        everywhere_later(&|v| !a.contains(&v) || b.contains(&v))
+      }
+      _ => self.forcing(&super::expand(e)),
+    }
+  }
+}
+
+impl std::fmt::Display for KripkeModel {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let stages = self.stages.iter().map(|w| w.to_string()).collect::<Vec<_>>().join(", ");
+    // Every stage comes at or after itself, so only the rest of the order is worth listing.
+    let order = self
+      .order
+      .iter()
+      .filter(|(w, v)| w != v)
+      .map(|(w, v)| format!("{}≤{}", w, v))
+      .collect::<Vec<_>>()
+      .join(", ");
+    writeln!(f, "W = {{{}}}", stages)?;
+    write!(f, "≤ = {{{}}}", order)?;
+    for w in &self.stages {
+      let trues = self
+        .valuation
+        .get(w)
+        .map(|trues| trues.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "))
+        .unwrap_or_default();
+      write!(f, "\n{} ⊩ {{{}}}", w, trues)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+
+  fn forces(model: &KripkeModel, s: &str, w: Stage) -> bool {
+    model.forces(w, &ClassicalParser::parse_expression(s).unwrap())
+  }
+
+  #[test]
+  fn test_forces() {
+    let mut model = KripkeModel::new();
+    model.add_order(0, 1);
+    model.set_true(1, Variable::new("p"));
+    assert!(model.is_persistent());
+    assert!(!forces(&model, "p v ~p", 0));
+    assert!(forces(&model, "p v ~p", 1));
+    assert!(forces(&model, "~~p", 0));
+    assert!(!forces(&model, "~~p -> p", 0));
+    assert!(forces(&model, "p -> p", 0));
+    assert!(!forces(&model, "p <-> ~~p", 0));
+    assert!(forces(&model, "p nand ~p", 0));
+    assert!(forces(&model, "p xor ~p", 0));
+    let forcing = |s: &str| {
+      let e = ClassicalParser::parse_expression(s).unwrap();
+      model.forcing(&e).into_iter().collect::<Vec<_>>()
+    };
+    assert_eq!(forcing("p v ~p"), vec![1]);
+    assert_eq!(forcing("~~p ^ (~p -> F)"), vec![0, 1]);
+
+    model.set_true(0, Variable::new("q"));
+    assert!(!model.is_persistent());
+  }
+
+  #[test]
+  fn test_order_is_transitive() {
+    let mut model = KripkeModel::new();
+    model.add_order(1, 2);
+    model.add_order(0, 1);
+    assert!(model.order.contains(&(0, 2)));
+    assert_eq!(model.later(0).collect::<Vec<_>>(), vec![0, 1, 2]);
+    assert_eq!(model.to_string(), "W = {0, 1, 2}\n≤ = {0≤1, 0≤2, 1≤2}\n0 ⊩ {}\n1 ⊩ {}\n2 ⊩ {}");
+  }
+}
@@ -0,0 +1,76 @@
+//! Intuitionistic propositional logic, following chapter 6 of Priest.
+//!
+//! Sentences are the classical ones, but read over Kripke interpretations whose stages only ever add to what is
+//! established: a negation or conditional is established at a stage only if it holds at every later stage as well.
+//! Validity is decided by the refutation search in `prover`, which returns a countermodel when there is one.
+
+pub mod countermodel;
+pub mod kripke;
+pub mod prover;
+
+use super::classical::{self, Expression};
+use super::{Logic, LogicResult, ParseError};
+
+pub use super::formula::Variable;
+
+pub type Theorem = classical::Theorem;
+
+pub struct IntuitionisticLogic;
+
+impl Logic for IntuitionisticLogic {
+  fn name(&self) -> &'static str {
+    "Intuitionistic"
+  }
+
+  fn is_valid_theorem(&self, t: &str) -> LogicResult {
+    let thm = classical::parse(t)?;
+    let (assumptions, conclusion) = (thm.assumptions(), thm.conclusion());
+    let countermodel = prover::refute(assumptions, conclusion)
+      .map_err(|_| ParseError::undecided(t, "the search for a countermodel grew too large to decide this"))?;
+    // Like the other logics, only claim non-entailment once the countermodel has been checked.
+    let valid = match countermodel {
+      None => true,
+      Some(c) if c.is_countermodel(assumptions, conclusion) => false,
+      Some(_) => return Err(ParseError::undecided(t, "the countermodel found doesn't refute this")),
+    };
+    Ok((format!("{}", thm), thm.holds(valid)))
+  }
+}
+
+/// Rewrites a sentence whose main connective is `↔`, `↑`, `↓` or `⊕` in terms of the others, which intuitionistic
+/// logic doesn't give the classical equivalences between: `A ↔ B` is `(A → B) ∧ (B → A)`, `A ↑ B` is `¬(A ∧ B)`,
+/// `A ↓ B` is `¬(A ∨ B)` and `A ⊕ B` is `¬(A ↔ B)`.  Anything else is left as it is.
+pub fn expand(e: &Expression) -> Expression {
+  use Expression::*;
+  let boxed = |e: Expression| Box::new(e);
+  match e {
+    Biconditional(a, b) => And(
+      boxed(Conditional(a.clone(), b.clone())),
+      boxed(Conditional(b.clone(), a.clone())),
+    ),
+    Nand(a, b) => Negated(boxed(And(a.clone(), b.clone()))),
+    Nor(a, b) => Negated(boxed(Or(a.clone(), b.clone()))),
+    Xor(a, b) => Negated(boxed(Biconditional(a.clone(), b.clone()))),
+    e => e.clone(),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_logic() {
+    let holds = |t: &str| IntuitionisticLogic.is_valid_theorem(t).unwrap().1;
+    assert!(holds("|- p -> p"));
+    assert!(holds("|/- p v ~p"));
+    assert!(holds("|/- ~~p -> p"));
+    assert!(holds("|- p -> ~~p"));
+    assert!(holds("~~~p |- ~p"));
+    assert!(holds("p v q, ~p |- q"));
+    assert!(!holds("|- ((p -> q) -> p) -> p"));
+    assert!(holds("|/- ((~r v (r v F)) -> ((q nand p) <-> (r <-> s))) xor (((T -> F) <-> r) v ~s)"));
+    let chains = "|- (p1 xor p2 xor p3 xor p4 xor p5 xor p6) <-> (p6 xor p5 xor p4 xor p3 xor p2 xor p1)";
+    assert!(IntuitionisticLogic.is_valid_theorem(chains).is_err(), "the search gives up");
+  }
+}
@@ -0,0 +1,330 @@
+//! A terminating refutation search for intuitionistic logic, in the style of a signed tableau.
+//!
+//! Each stage of the search has some sentences marked true and some marked false.  Everything true at a stage stays
+//! true later, but false sentences don't carry over.  Sentences are decomposed at their stage until only letters and
+//! false negations and conditionals are left; each of those gets a later stage where its antecedent is true and its
+//! consequent false.  The search fails, and the inference is valid, as soon as one stage can't be had.  Otherwise the
+//! stages make up a countermodel.
+//!
+//! A false `¬A` or `A → B` whose antecedent is already true is dealt with at its own stage, since truths persist.  So
+//! every later stage has more true sentences than the one before, all drawn from the subformulas of the inference,
+//! and the search always finishes.  It can still take exponentially long, since the same stage turns up along many
+//! paths, so what was found for each stage is remembered, and after `MAX_STEPS` steps we give up.
+
+use super::countermodel::Countermodel;
+use super::kripke::{KripkeModel, Stage};
+use super::{expand, Variable};
+use crate::logic::classical::Expression;
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+
+/// The most sentences the search will decompose, counting each time at each stage, before giving up.
+pub const MAX_STEPS: usize = 20_000;
+
+/// The search decomposed `MAX_STEPS` sentences without finishing.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct GaveUp;
+
+/// Whether the assumptions intuitionistically entail the conclusion.
+pub fn is_valid(assumptions: &[Expression], conclusion: &Expression) -> Result<bool, GaveUp> {
+  Ok(search(assumptions, conclusion)?.is_none())
+}
+
+/// A countermodel to the inference from `assumptions` to `conclusion`, if there is one.
+pub fn refute(assumptions: &[Expression], conclusion: &Expression) -> Result<Option<Countermodel>, GaveUp> {
+  let tree = match search(assumptions, conclusion)? {
+    Some(tree) => tree,
+    None => return Ok(None),
+  };
+  let mut model = KripkeModel::new();
+  tree.add_to(&mut model, &mut HashMap::new());
+  Ok(Some(Countermodel { model }))
+}
+
+fn search(assumptions: &[Expression], conclusion: &Expression) -> Result<Option<Rc<StageTree>>, GaveUp> {
+  let mut search = Search::default();
+  let start = Sequent {
+    trues: assumptions.iter().map(|e| search.number(e)).collect(),
+    falses: Some(search.number(conclusion)).into_iter().collect(),
+    ..Sequent::default()
+  };
+  start.refute(&mut search)
+}
+
+/// A subformula of the inference, with `↔`, `↑`, `↓` and `⊕` written out, and its own subformulas given by their
+/// numbers in `Search::sentences`.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+enum Sentence {
+  Letter(Variable),
+  Top,
+  Bottom,
+  Not(usize),
+  And(usize, usize),
+  Or(usize, usize),
+  If(usize, usize),
+}
+
+/// The numbers of the sentences marked true and marked false at a stage.
+type Marks = (BTreeSet<usize>, BTreeSet<usize>);
+
+/// What the search has found out so far, shared by all of its stages.
+#[derive(Default)]
+struct Search {
+  sentences: Vec<Sentence>,
+  numbers: HashMap<Sentence, usize>,
+  /// The stages looked at so far, by their true and false sentences, and the trees of stages found for them.
+  known: HashMap<Marks, Option<Rc<StageTree>>>,
+  /// How many sentences have been decomposed so far.
+  steps: usize,
+}
+
+impl Search {
+  fn number(&mut self, e: &Expression) -> usize {
+    use Expression::*;
+    let sentence = match e {
+      Variable(v) => Sentence::Letter(*v),
+      Top => Sentence::Top,
+      Bottom => Sentence::Bottom,
+      Negated(a) => Sentence::Not(self.number(a)),
+      And(a, b) => Sentence::And(self.number(a), self.number(b)),
+      Or(a, b) => Sentence::Or(self.number(a), self.number(b)),
+      Conditional(a, b) => Sentence::If(self.number(a), self.number(b)),
+      e => return self.number(&expand(e)),
+    };
+    if let Some(n) = self.numbers.get(&sentence) {
+      return *n;
+    }
+    self.sentences.push(sentence);
+    self.numbers.insert(sentence, self.sentences.len() - 1);
+    self.sentences.len() - 1
+  }
+}
+
+/// What decomposing a marked sentence does to its stage: either adds true and false sentences, or splits the search
+/// in two, each way adding some.
+enum Step {
+  Add(Vec<usize>, Vec<usize>),
+  Split([(Vec<usize>, Vec<usize>); 2]),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Sequent {
+  trues: BTreeSet<usize>,
+  falses: BTreeSet<usize>,
+  /// The sentences already decomposed at this stage, with whether they were marked true.
+  used: BTreeSet<(bool, usize)>,
+}
+
+/// A stage of a countermodel: the letters established there, and the stages just after it.
+struct StageTree {
+  letters: Vec<Variable>,
+  later: Vec<Rc<StageTree>>,
+}
+
+impl StageTree {
+  /// Numbers the stages depth first, returning the number given to this one.  Stages found for the same sequent are
+  /// shared between trees, and are only added once.
+  fn add_to(self: &Rc<Self>, model: &mut KripkeModel, added: &mut HashMap<*const StageTree, Stage>) -> Stage {
+    if let Some(w) = added.get(&Rc::as_ptr(self)) {
+      return *w;
+    }
+    let w = added.len();
+    added.insert(Rc::as_ptr(self), w);
+    model.add_stage(w);
+    for p in &self.letters {
+      model.set_true(w, *p);
+    }
+    for tree in &self.later {
+      let v = tree.add_to(model, added);
+      model.add_order(w, v);
+    }
+    w
+  }
+}
+
+impl Sequent {
+  fn add(&mut self, trues: &[usize], falses: &[usize]) {
+    self.trues.extend(trues);
+    self.falses.extend(falses);
+  }
+
+  fn is_closed(&self, search: &Search) -> bool {
+    self.trues.iter().any(|n| search.sentences[*n] == Sentence::Bottom || self.falses.contains(n))
+      || self.falses.iter().any(|n| search.sentences[*n] == Sentence::Top)
+  }
+
+  fn step(&self, search: &Search, marked_true: bool, n: usize) -> Option<Step> {
+    use Sentence::*;
+    let step = match (marked_true, search.sentences[n]) {
+      (_, Letter(_)) | (_, Top) | (_, Bottom) => return None,
+      (true, And(a, b)) => Step::Add(vec![a, b], vec![]),
+      (true, Or(a, b)) => Step::Split([(vec![a], vec![]), (vec![b], vec![])]),
+      (true, Not(a)) => Step::Add(vec![], vec![a]),
+      // The conditional stays true, and so is decomposed again at each later stage.
+      (true, If(a, b)) => Step::Split([(vec![], vec![a]), (vec![b], vec![])]),
+      (false, And(a, b)) => Step::Split([(vec![], vec![a]), (vec![], vec![b])]),
+      (false, Or(a, b)) => Step::Add(vec![], vec![a, b]),
+      (false, Not(a)) if self.trues.contains(&a) => Step::Add(vec![], vec![]),
+      (false, If(a, b)) if self.trues.contains(&a) => Step::Add(vec![], vec![b]),
+      (false, Not(_)) | (false, If(_, _)) => return None,
+    };
+    Some(step)
+  }
+
+  /// The next sentence to decompose, preferring those that don't split the search.
+  fn next_step(&self, search: &Search) -> Option<((bool, usize), Step)> {
+    let marked = self.trues.iter().map(|n| (true, *n)).chain(self.falses.iter().map(|n| (false, *n)));
+    let mut split = None;
+    for key in marked.filter(|key| !self.used.contains(key)) {
+      match self.step(search, key.0, key.1) {
+        Some(Step::Add(trues, falses)) => return Some((key, Step::Add(trues, falses))),
+        Some(step) if split.is_none() => split = Some((key, step)),
+        _ => {}
+      }
+    }
+    split
+  }
+
+  /// Decomposes everything at this stage and then looks for the later stages it needs, giving the tree of stages
+  /// they make up, or `None` if there's no way to have them all.
+  fn refute(self, search: &mut Search) -> Result<Option<Rc<StageTree>>, GaveUp> {
+    let key = (self.trues.clone(), self.falses.clone());
+    if let Some(tree) = search.known.get(&key) {
+      return Ok(tree.clone());
+    }
+    let tree = self.decompose(search)?;
+    search.known.insert(key, tree.clone());
+    Ok(tree)
+  }
+
+  fn decompose(mut self, search: &mut Search) -> Result<Option<Rc<StageTree>>, GaveUp> {
+    while !self.is_closed(search) {
+      search.steps += 1;
+      if search.steps > MAX_STEPS {
+        return Err(GaveUp);
+      }
+      let (key, step) = match self.next_step(search) {
+        Some(next) => next,
+        None => return self.refute_later(search),
+      };
+      self.used.insert(key);
+      match step {
+        Step::Add(trues, falses) => self.add(&trues, &falses),
+        Step::Split(ways) => {
+          for (trues, falses) in ways.iter() {
+            let mut sequent = self.clone();
+            sequent.add(trues, falses);
+            if let Some(tree) = sequent.refute(search)? {
+              return Ok(Some(tree));
+            }
+          }
+          return Ok(None);
+        }
+      }
+    }
+    Ok(None)
+  }
+
+  /// Every false negation and conditional left needs a later stage where its antecedent is true and its consequent
+  /// false, and where everything true here is still true.
+  fn refute_later(&self, search: &mut Search) -> Result<Option<Rc<StageTree>>, GaveUp> {
+    let needed = self
+      .falses
+      .iter()
+      .filter_map(|n| match search.sentences[*n] {
+        Sentence::Not(a) => Some((a, None)),
+        Sentence::If(a, b) => Some((a, Some(b))),
+        _ => None,
+      })
+      .filter(|(a, _)| !self.trues.contains(a))
+      .collect::<Vec<_>>();
+    let mut later = Vec::new();
+    for (a, b) in needed {
+      let sequent = Sequent {
+        trues: self.trues.iter().cloned().chain(Some(a)).collect(),
+        falses: b.into_iter().collect(),
+        ..Sequent::default()
+      };
+      match sequent.refute(search)? {
+        Some(tree) => later.push(tree),
+        None => return Ok(None),
+      }
+    }
+    let letters = self
+      .trues
+      .iter()
+      .filter_map(|n| match search.sentences[*n] {
+        Sentence::Letter(v) => Some(v),
+        _ => None,
+      })
+      .collect();
+    Ok(Some(Rc::new(StageTree { letters, later })))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+
+  fn parse(sentences: &[&str]) -> Vec<Expression> {
+    sentences.iter().map(|s| ClassicalParser::parse_expression(s).unwrap()).collect()
+  }
+
+  fn countermodel(assumptions: &[&str], conclusion: &str) -> Option<Countermodel> {
+    refute(&parse(assumptions), &parse(&[conclusion])[0]).unwrap()
+  }
+
+  #[test]
+  fn test_valid_inferences() {
+    let valid = |a: &[&str], c: &str| countermodel(a, c).is_none();
+    assert!(valid(&[], "p -> p"));
+    assert!(valid(&[], "p -> ~~p"));
+    assert!(valid(&[], "~~(p v ~p)"));
+    assert!(valid(&[], "~(p ^ ~p)"));
+    assert!(valid(&["~~~p"], "~p"));
+    assert!(valid(&["p -> q", "q -> r"], "p -> r"));
+    assert!(valid(&["p v q", "~p"], "q"));
+    assert!(valid(&[], "(p v q -> r) <-> (p -> r) ^ (q -> r)"));
+    assert!(valid(&[], "~(p v q) <-> ~p ^ ~q"));
+    assert!(valid(&["F"], "p"));
+    assert!(valid(&[], "T"));
+    assert!(valid(&[], "p nand ~p"));
+  }
+
+  #[test]
+  fn test_invalid_inferences() {
+    let cases: &[(&[&str], &str)] = &[
+      (&[], "p v ~p"),
+      (&[], "~~p -> p"),
+      (&[], "((p -> q) -> p) -> p"),
+      (&[], "~(p ^ q) -> ~p v ~q"),
+      (&[], "(p -> q) v (q -> p)"),
+      (&[], "(~p -> q v r) -> (~p -> q) v (~p -> r)"),
+      (&["~p -> q"], "p v q"),
+      (&["~~p"], "p"),
+      (&[], "F"),
+    ];
+    for (assumptions, conclusion) in cases {
+      let c = countermodel(assumptions, conclusion).expect("invalid inferences have countermodels");
+      assert!(c.is_countermodel(&parse(assumptions), &parse(&[conclusion])[0]), "{}\n{}", conclusion, c);
+    }
+  }
+
+  #[test]
+  fn test_countermodels() {
+    let c = countermodel(&[], "p v ~p").unwrap();
+    assert_eq!(c.to_string(), "W = {0, 1}\n≤ = {0≤1}\n0 ⊩ {}\n1 ⊩ {p}");
+    let c = countermodel(&[], "~~p -> p").unwrap();
+    assert_eq!(c.to_string(), "W = {0, 1, 2}\n≤ = {0≤1, 0≤2, 1≤2}\n0 ⊩ {}\n1 ⊩ {}\n2 ⊩ {p}");
+  }
+
+  #[test]
+  fn test_larger_inferences() {
+    // The same stages turn up along many paths here.
+    let conclusion = "((~r v (r v F)) -> ((q nand p) <-> (r <-> s))) xor (((T -> F) <-> r) v ~s)";
+    let c = countermodel(&[], conclusion).unwrap();
+    assert!(c.is_countermodel(&[], &parse(&[conclusion])[0]), "{}", c);
+    assert!(countermodel(&[], "(p1 xor p2 xor p3 xor p4 xor p5) <-> (p5 xor p4 xor p3 xor p2 xor p1)").is_none());
+  }
+}
@@ -0,0 +1,164 @@
+//! LaTeX for problem sets and slides.  Formulas and theorems come out as math-mode source, to go between `$`s, and
+//! tableaux as a `forest` environment.  Anything here needs `amssymb` (for `\nvdash` and `\Box`) and `forest`;
+//! `document` wraps a snippet in a standalone document that loads both.
+
+use super::formula::Theorem;
+use super::tableau::{NodeId, Tree};
+
+pub trait Latex {
+  fn latex(&self) -> String;
+}
+
+impl<F: Latex> Latex for Theorem<F> {
+  fn latex(&self) -> String {
+    let op = match self {
+      Self::Proves { .. } => "\\vdash",
+      Self::DoesNotProve { .. } => "\\nvdash",
+    };
+    let assumptions = self.assumptions().iter().map(|a| a.latex()).collect::<Vec<_>>().join(", ");
+    if assumptions.is_empty() {
+      format!("{} {}", op, self.conclusion().latex())
+    } else {
+      format!("{} {} {}", assumptions, op, self.conclusion().latex())
+    }
+  }
+}
+
+/// Follows the plain-text layout of a tree: numbered nodes with their `[rule, source]`, and closed branches ending in
+/// `×`.  Runs of nodes without a split share one `forest` node, so the tree only branches where the tableau does.
+impl<T: Latex, R: Latex> Latex for Tree<T, R> {
+  fn latex(&self) -> String {
+    let mut s = String::from("\\begin{forest}\n  for tree={align=center}\n");
+    if let Some(root) = self.root() {
+      latex_from(self, root, 1, &mut s);
+    }
+    s.push_str("\\end{forest}");
+    s
+  }
+}
+
+fn latex_from<T: Latex, R: Latex>(tree: &Tree<T, R>, id: NodeId, depth: usize, s: &mut String) {
+  let mut lines = Vec::new();
+  let mut current = id;
+  loop {
+    let node = tree.node(current);
+    let source = node.source.map(|s| format!(", {}", s + 1)).unwrap_or_default();
+    lines.push(format!(
+      "{}.\\ ${}$ \\quad [${}${}]",
+      current + 1,
+      node.content.latex(),
+      node.rule.latex(),
+      source
+    ));
+    match node.children.as_slice() {
+      [child] => current = *child,
+      [] if node.closed => {
+        lines.push("$\\times$".to_owned());
+        break;
+      }
+      [] => {
+        lines.push("$\\uparrow$".to_owned());
+        break;
+      }
+      _ => break,
+    }
+  }
+  let indent = "  ".repeat(depth);
+  s.push_str(&format!("{}[{{{}}}\n", indent, lines.join(" \\\\ ")));
+  for child in &tree.node(current).children {
+    latex_from(tree, *child, depth + 1, s);
+  }
+  s.push_str(&format!("{}]\n", indent));
+}
+
+/// Rewrites the Unicode symbols we print, such as the rule names in tableaux, as LaTeX commands.
+pub fn from_unicode(s: &str) -> String {
+  let mut latex = String::new();
+  for c in s.chars() {
+    let command = match c {
+      '¬' => "\\neg",
+      '∧' => "\\wedge",
+      '∨' => "\\vee",
+      '→' => "\\rightarrow",
+      '↔' => "\\leftrightarrow",
+      '↑' => "\\uparrow",
+      '↓' => "\\downarrow",
+      '⊕' => "\\oplus",
+      '◻' => "\\Box",
+      '◇' => "\\Diamond",
+      '⊤' => "\\top",
+      '⊥' => "\\bot",
+      '−' => "-",
+      'η' => "\\eta",
+      'ρ' => "\\rho",
+      'σ' => "\\sigma",
+      'τ' => "\\tau",
+      'ε' => "\\varepsilon",
+      'δ' => "\\delta",
+      'λ' => "\\lambda",
+      '⁻' => "^{-}",
+      '⟨' => "\\langle",
+      '⟩' => "\\rangle",
+      c => {
+        latex.push(c);
+        continue;
+      }
+    };
+    latex.push_str(command);
+    latex.push(' ');
+  }
+  latex.trim_end().to_owned()
+}
+
+/// A complete document around `body`, which should be text-mode LaTeX such as a `forest` or `$...$`.
+pub fn document(body: &str) -> String {
+  format!(
+    "\\documentclass{{standalone}}\n\\usepackage{{amssymb}}\n\\usepackage{{forest}}\n\\begin{{document}}\n{}\n\\end{{document}}\n",
+    body
+  )
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+  use crate::logic::classical::tableau::Tableau as ClassicalTableau;
+  use crate::logic::modal::parser::ModalParser;
+  use crate::logic::modal::tableau::Tableau as ModalTableau;
+  use crate::logic::modal::FrameConditions;
+
+  #[test]
+  fn test_formulas_and_theorems() {
+    let e = ClassicalParser::parse_expression("~(p ^ q) -> r").unwrap();
+    assert_eq!(e.latex(), r"\neg (p \wedge q) \rightarrow r");
+    let thm = ModalParser::parse_theorem("[]p, <>q |- <>(p ^ q)").unwrap();
+    assert_eq!(thm.latex(), r"\Box p, \Diamond q \vdash \Diamond (p \wedge q)");
+    let thm = ModalParser::parse_theorem("|/- []p -> p").unwrap();
+    assert_eq!(thm.latex(), r"\nvdash \Box p \rightarrow p");
+  }
+
+  #[test]
+  fn test_tableaux() {
+    let t = ClassicalTableau::for_theorem(&ClassicalParser::parse_theorem("p v q |- p").unwrap());
+    assert_eq!(
+      t.latex(),
+      [
+        r"\begin{forest}",
+        r"  for tree={align=center}",
+        r"  [{1.\ $p \vee q$ \quad [$A$] \\ 2.\ $\neg p$ \quad [$\neg C$]}",
+        r"    [{3.\ $p$ \quad [$\vee$, 1] \\ $\times$}",
+        r"    ]",
+        r"    [{4.\ $q$ \quad [$\vee$, 1] \\ $\uparrow$}",
+        r"    ]",
+        r"  ]",
+        r"\end{forest}",
+      ]
+      .join("\n")
+    );
+
+    let t = ModalTableau::for_theorem(&ModalParser::parse_theorem("|- <>p").unwrap(), FrameConditions::D);
+    let latex = t.latex();
+    assert!(latex.contains(r"$\neg \Diamond p, 0$"), "{}", latex);
+    assert!(latex.contains(r"$0r1$ \quad [$\eta$]"), "{}", latex);
+  }
+}
@@ -0,0 +1,411 @@
+//! Many-valued propositional logics, given by logical matrices: a set of truth values, the designated ones among them,
+//! and a truth function on those values for each connective.  An inference is valid when every assignment of values to
+//! sentence letters that designates all the assumptions also designates the conclusion, which we check by trying them
+//! all.
+//!
+//! The presets are the three- and four-valued logics of chapters 7 and 8 of Priest.
+
+pub mod star;
+pub mod tableau;
+
+use super::classical::{self, Expression};
+use super::formula::{Formula, Operator, Shape, Variable};
+use super::{Logic, LogicResult, ParseError};
+use std::collections::{BTreeMap, BTreeSet};
+use tableau::{Closure, Tableau};
+
+/// A truth value, as its place in its matrix's list of values.
+pub type Value = usize;
+
+/// The most assignments we'll try for one inference: enough for 12 sentence letters with three values, or 10 with four.
+pub const MAX_ASSIGNMENTS: usize = 1 << 20;
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Matrix {
+  name: &'static str,
+  values: Vec<String>,
+  designated: Vec<bool>,
+  /// For each connective given a truth function, its value for each row, where the row for arguments `a` and `b` is
+  /// `a * n + b` for `n` values.
+  tables: BTreeMap<Operator, Vec<Value>>,
+}
+
+impl Matrix {
+  /// A matrix with the given values, of which `⊤` is the first and `⊥` the last.  It needs truth functions for at
+  /// least `¬`, `∧` and `∨`; other connectives default to their usual definitions in terms of those, with `A → B`
+  /// as `¬A ∨ B` and `A ↔ B` as `(A → B) ∧ (B → A)`.
+  pub fn new(name: &'static str, values: &[&str], designated: &[&str]) -> Self {
+    assert!(designated.iter().all(|d| values.contains(d)), "designated values must be values");
+    Self {
+      name,
+      values: values.iter().map(|v| v.to_string()).collect(),
+      designated: values.iter().map(|v| designated.contains(v)).collect(),
+      tables: BTreeMap::new(),
+    }
+  }
+
+  pub fn unary(mut self, op: Operator, f: impl Fn(Value) -> Value) -> Self {
+    assert!(op.is_unary(), "{:?} is not unary", op);
+    self.tables.insert(op, (0..self.values.len()).map(f).collect());
+    self
+  }
+
+  pub fn binary(mut self, op: Operator, f: impl Fn(Value, Value) -> Value) -> Self {
+    assert!(!op.is_unary(), "{:?} is not binary", op);
+    let n = self.values.len();
+    self.tables.insert(op, (0..n * n).map(|row| f(row / n, row % n)).collect());
+    self
+  }
+
+  /// Strong Kleene logic: `i` is neither true nor false.
+  pub fn k3() -> Self {
+    Self::three_valued("K3 (matrix)", &["1"])
+  }
+
+  /// The Logic of Paradox: `i` is both true and false, and so designated.
+  pub fn lp() -> Self {
+    Self::three_valued("LP (matrix)", &["1", "i"])
+  }
+
+  /// Łukasiewicz's three-valued logic, K3 with a conditional making `A → A` valid.
+  pub fn l3() -> Self {
+    Self::three_valued("Ł3", &["1"]).binary(Operator::Conditional, by_degree(|a, b| 2.min(2 - a + b)))
+  }
+
+  /// LP with a conditional that's true only if the consequent is at least as true as the antecedent, and then as
+  /// true as either's negation or the consequent.
+  pub fn rm3() -> Self {
+    let conditional = |a: Value, b: Value| if a <= b { (2 - a).max(b) } else { (2 - a).min(b) };
+    Self::three_valued("RM3", &["1", "i"]).binary(Operator::Conditional, by_degree(conditional))
+  }
+
+  /// First Degree Entailment: a sentence may be true, false, both (`b`) or neither (`n`), and is designated when it is
+  /// at least true.
+  pub fn fde() -> Self {
+    // Each value as whether it is true and whether it is false.
+    const VALUES: [(bool, bool); 4] = [(true, false), (true, true), (false, false), (false, true)];
+    let value = |v: (bool, bool)| VALUES.iter().position(|w| *w == v).unwrap();
+    Self::new("FDE (matrix)", &["1", "b", "n", "0"], &["1", "b"])
+      .unary(Operator::Not, |a| value((VALUES[a].1, VALUES[a].0)))
+      .binary(Operator::And, |a, b| {
+        let (a, b) = (VALUES[a], VALUES[b]);
+        value((a.0 && b.0, a.1 || b.1))
+      })
+      .binary(Operator::Or, |a, b| {
+        let (a, b) = (VALUES[a], VALUES[b]);
+        value((a.0 || b.0, a.1 && b.1))
+      })
+  }
+
+  pub fn presets() -> Vec<Self> {
+    vec![Self::k3(), Self::lp(), Self::l3(), Self::rm3(), Self::fde()]
+  }
+
+  /// The values `1`, `i` and `0`, with `¬` turning their order around, `∧` the least true and `∨` the most.
+  fn three_valued(name: &'static str, designated: &[&str]) -> Self {
+    Self::new(name, &["1", "i", "0"], designated)
+      .unary(Operator::Not, |a| 2 - a)
+      .binary(Operator::And, by_degree(|a, b| a.min(b)))
+      .binary(Operator::Or, by_degree(|a, b| a.max(b)))
+  }
+
+  pub fn values(&self) -> &[String] {
+    &self.values
+  }
+
+  /// An assignment of these values to sentence letters.
+  pub fn assignment(&self, values: BTreeMap<Variable, Value>) -> Assignment {
+    assert!(values.values().all(|v| *v < self.values.len()), "no such value");
+    Assignment {
+      values,
+      names: self.values.clone(),
+    }
+  }
+
+  pub fn is_designated(&self, v: Value) -> bool {
+    self.designated[v]
+  }
+
+  /// The value of `op` applied to `arguments`.
+  pub fn apply(&self, op: Operator, arguments: &[Value]) -> Value {
+    if let Some(table) = self.tables.get(&op) {
+      return match arguments {
+        [a] => table[*a],
+        [a, b] => table[a * self.values.len() + b],
+        _ => panic!("{:?} takes one or two arguments", op),
+      };
+    }
+    let not = |a| self.apply(Operator::Not, &[a]);
+    match (op, arguments) {
+      (Operator::Conditional, [a, b]) => self.apply(Operator::Or, &[not(*a), *b]),
+      (Operator::Biconditional, [a, b]) => {
+        let there = self.apply(Operator::Conditional, &[*a, *b]);
+        let back = self.apply(Operator::Conditional, &[*b, *a]);
+        self.apply(Operator::And, &[there, back])
+      }
+      (Operator::Nand, [a, b]) => not(self.apply(Operator::And, &[*a, *b])),
+      (Operator::Nor, [a, b]) => not(self.apply(Operator::Or, &[*a, *b])),
+      (Operator::Xor, [a, b]) => not(self.apply(Operator::Biconditional, &[*a, *b])),
+      _ => panic!("{} has no truth function for {}", self.name, op),
+    }
+  }
+
+  pub fn eval<F: Formula>(&self, f: &F, assignment: &Assignment) -> Value {
+    match f.shape() {
+      Shape::Variable(v) => assignment.values[&v],
+      Shape::Constant(true) => 0,
+      Shape::Constant(false) => self.values.len() - 1,
+      Shape::Unary(op, a) => self.apply(op, &[self.eval(a, assignment)]),
+      Shape::Binary(op, a, b) => self.apply(op, &[self.eval(a, assignment), self.eval(b, assignment)]),
+    }
+  }
+
+  /// Whether `countermodel` can try every assignment to the sentence letters of an inference, there being no more than
+  /// `MAX_ASSIGNMENTS` of them.
+  pub fn can_decide(&self, assumptions: &[Expression], conclusion: &Expression) -> bool {
+    let letters = letters(assumptions, conclusion).len();
+    self.values.len().checked_pow(letters as u32).is_some_and(|count| count <= MAX_ASSIGNMENTS)
+  }
+
+  /// An assignment designating every assumption but not the conclusion, if there is one.  This tries every assignment
+  /// in turn, so it panics unless `can_decide`.
+  pub fn countermodel(&self, assumptions: &[Expression], conclusion: &Expression) -> Option<Assignment> {
+    assert!(self.can_decide(assumptions, conclusion), "too many sentence letters to try every assignment");
+    let letters = letters(assumptions, conclusion);
+    let n = self.values.len();
+    let count = n.pow(letters.len() as u32);
+    (0..count)
+      .map(|i| {
+        let values = letters
+          .iter()
+          .enumerate()
+          .map(|(j, v)| (*v, i / n.pow((letters.len() - 1 - j) as u32) % n))
+          .collect();
+        self.assignment(values)
+      })
+      .find(|a| self.is_countermodel(assumptions, conclusion, a))
+  }
+
+  /// Whether `assignment` gives every sentence letter of an inference a value, designating every assumption but not
+  /// the conclusion.
+  pub fn is_countermodel(&self, assumptions: &[Expression], conclusion: &Expression, assignment: &Assignment) -> bool {
+    let designated = |e: &Expression| self.is_designated(self.eval(e, assignment));
+    letters(assumptions, conclusion).iter().all(|v| assignment.values.contains_key(v))
+      && assumptions.iter().all(designated)
+      && !designated(conclusion)
+  }
+
+  /// Panics unless `can_decide`.
+  pub fn is_valid(&self, assumptions: &[Expression], conclusion: &Expression) -> bool {
+    self.countermodel(assumptions, conclusion).is_none()
+  }
+}
+
+impl Logic for Matrix {
+  fn name(&self) -> &'static str {
+    self.name
+  }
+
+  fn is_valid_theorem(&self, t: &str) -> LogicResult {
+    let thm = classical::parse(t)?;
+    if !self.can_decide(thm.assumptions(), thm.conclusion()) {
+      return Err(ParseError::undecided(t, "too many sentence letters to try every assignment"));
+    }
+    let valid = match self.countermodel(thm.assumptions(), thm.conclusion()) {
+      None => true,
+      Some(a) if self.is_countermodel(thm.assumptions(), thm.conclusion(), &a) => false,
+      Some(_) => return Err(ParseError::undecided(t, "the assignment found doesn't refute this")),
+    };
+    Ok((format!("{}", thm), thm.holds(valid)))
+  }
+}
+
+/// The sentence letters of an inference, in order.
+fn letters(assumptions: &[Expression], conclusion: &Expression) -> Vec<Variable> {
+  let letters = assumptions.iter().chain(Some(conclusion)).flat_map(|e| e.variables());
+  letters.collect::<BTreeSet<_>>().into_iter().collect()
+}
+
+/// FDE, K3 or LP, decided by signed tableaux rather than by trying every assignment.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct TableauLogic {
+  name: &'static str,
+  closure: Closure,
+}
+
+impl TableauLogic {
+  pub const FDE: Self = Self::new("FDE", Closure::Fde);
+  pub const K3: Self = Self::new("K3", Closure::K3);
+  pub const LP: Self = Self::new("LP", Closure::Lp);
+
+  pub const SYSTEMS: [Self; 3] = [Self::FDE, Self::K3, Self::LP];
+
+  pub const fn new(name: &'static str, closure: Closure) -> Self {
+    Self { name, closure }
+  }
+
+  pub fn closure(&self) -> Closure {
+    self.closure
+  }
+}
+
+impl Logic for TableauLogic {
+  fn name(&self) -> &'static str {
+    self.name
+  }
+
+  fn is_valid_theorem(&self, t: &str) -> LogicResult {
+    let thm = classical::parse(t)?;
+    let tableau = Tableau::for_theorem(&thm, self.closure);
+    if tableau.is_closed() {
+      return Ok((format!("{}", thm), thm.holds(true)));
+    }
+    if !tableau.is_complete() {
+      return Err(ParseError::undecided(t, "the tableau grew too large to decide this"));
+    }
+    // As in the modal logics, only claim non-entailment once the open branch's countermodel has been checked.
+    match tableau.countermodel() {
+      Some(a) if self.closure.matrix().is_countermodel(thm.assumptions(), thm.conclusion(), &a) => {
+        Ok((format!("{}", thm), thm.holds(false)))
+      }
+      _ => Err(ParseError::undecided(t, "the tableau's open branch doesn't describe a countermodel")),
+    }
+  }
+}
+
+/// A three-valued truth function given on degrees of truth, from 0 for `0` up to 2 for `1`, as one on the values
+/// `1`, `i` and `0` in that order.
+fn by_degree(f: impl Fn(Value, Value) -> Value) -> impl Fn(Value, Value) -> Value {
+  move |a, b| 2 - f(2 - a, 2 - b)
+}
+
+/// Values for sentence letters, shown with the names their matrix gives them.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Assignment {
+  pub values: BTreeMap<Variable, Value>,
+  names: Vec<String>,
+}
+
+impl Assignment {
+  pub fn name(&self, v: Variable) -> Option<&str> {
+    self.values.get(&v).map(|value| self.names[*value].as_str())
+  }
+}
+
+impl std::fmt::Display for Assignment {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let values = self
+      .values
+      .iter()
+      .map(|(v, value)| format!("v({}) = {}", v, self.names[*value]))
+      .collect::<Vec<_>>();
+    write!(f, "{}", values.join(", "))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn valid(m: &Matrix, t: &str) -> bool {
+    let thm = classical::parse(t).unwrap();
+    m.is_valid(thm.assumptions(), thm.conclusion())
+  }
+
+  fn countermodel(m: &Matrix, t: &str) -> Option<String> {
+    let thm = classical::parse(t).unwrap();
+    m.countermodel(thm.assumptions(), thm.conclusion()).map(|a| a.to_string())
+  }
+
+  #[test]
+  fn test_k3() {
+    let k3 = Matrix::k3();
+    assert_eq!(countermodel(&k3, "|- p v ~p"), Some("v(p) = i".to_string()));
+    assert_eq!(countermodel(&k3, "|- p -> p"), Some("v(p) = i".to_string()));
+    assert!(valid(&k3, "p, ~p |- q"));
+    assert!(valid(&k3, "p, p -> q |- q"));
+    assert!(valid(&k3, "~(p & q) |- ~p v ~q"));
+  }
+
+  #[test]
+  fn test_lp() {
+    let lp = Matrix::lp();
+    assert!(valid(&lp, "|- p v ~p"));
+    assert!(valid(&lp, "|- ~(p & ~p)"));
+    assert_eq!(countermodel(&lp, "p, ~p |- q"), Some("v(p) = i, v(q) = 0".to_string()));
+    assert_eq!(countermodel(&lp, "p, p -> q |- q"), Some("v(p) = i, v(q) = 0".to_string()));
+  }
+
+  #[test]
+  fn test_l3() {
+    let l3 = Matrix::l3();
+    assert!(valid(&l3, "|- p -> p"));
+    assert!(valid(&l3, "p, p -> q |- q"));
+    assert!(!valid(&l3, "|- p v ~p"));
+    assert!(!valid(&l3, "|- (p -> (p -> q)) -> (p -> q)"));
+  }
+
+  #[test]
+  fn test_rm3() {
+    let rm3 = Matrix::rm3();
+    assert!(valid(&rm3, "|- p -> p"));
+    assert!(valid(&rm3, "p, p -> q |- q"));
+    assert_eq!(countermodel(&rm3, "|- p -> (q -> p)"), Some("v(p) = i, v(q) = 1".to_string()));
+    assert!(!valid(&rm3, "p, ~p |- q"));
+  }
+
+  #[test]
+  fn test_fde() {
+    let fde = Matrix::fde();
+    assert!(!valid(&fde, "|- p v ~p"));
+    assert!(!valid(&fde, "|- p -> p"));
+    assert!(!valid(&fde, "p, ~p |- q"));
+    assert!(valid(&fde, "p & q |- p"));
+    assert!(valid(&fde, "~(p & q) |- ~p v ~q"));
+    assert!(valid(&fde, "~~p |- p"));
+    assert_eq!(countermodel(&fde, "p |- q v ~q"), Some("v(p) = 1, v(q) = n".to_string()));
+  }
+
+  #[test]
+  fn test_is_countermodel() {
+    let k3 = Matrix::k3();
+    let thm = classical::parse("p |- q").unwrap();
+    let p = Variable::new("p");
+    let q = Variable::new("q");
+    let is_countermodel = |values: &[(Variable, Value)]| {
+      k3.is_countermodel(thm.assumptions(), thm.conclusion(), &k3.assignment(values.iter().cloned().collect()))
+    };
+    assert!(is_countermodel(&[(p, 0), (q, 1)]));
+    assert!(!is_countermodel(&[(p, 1), (q, 1)]));
+    assert!(!is_countermodel(&[(p, 0), (q, 0)]));
+    assert!(!is_countermodel(&[(p, 0)]), "q has no value");
+  }
+
+  #[test]
+  fn test_logic() {
+    for m in Matrix::presets() {
+      assert!(m.is_valid_theorem("p & q |- q").unwrap().1, "{}", m.name());
+      assert!(m.is_valid_theorem("T").unwrap().1, "{}", m.name());
+      assert!(m.is_valid_theorem("p |/- F").unwrap().1, "{}", m.name());
+    }
+    assert!(Matrix::lp().is_valid_theorem("p v ~p").unwrap().1);
+    let conjunction = (1..=41).map(|i| format!("p{}", i)).collect::<Vec<_>>().join(" ^ ");
+    let e = Matrix::k3().is_valid_theorem(&conjunction).unwrap_err();
+    assert_eq!(e.message(), "too many sentence letters to try every assignment");
+    let premises = (1..=11).map(|i| format!("p{}", i)).collect::<Vec<_>>().join(", ");
+    assert!(Matrix::fde().is_valid_theorem(&format!("{} |- p1", premises)).is_err());
+    assert!(Matrix::k3().is_valid_theorem(&format!("{}, F |- p1", premises)).unwrap().1);
+    assert!(!Matrix::k3().is_valid_theorem("p v ~p").unwrap().1);
+    for logic in TableauLogic::SYSTEMS.iter() {
+      let matrix = logic.closure().matrix();
+      assert_ne!(logic.name(), matrix.name());
+      for t in &["p v ~p", "p, ~p |- q", "p, p -> q |/- q", "~(p v q) |- ~p ^ ~q"] {
+        assert_eq!(logic.is_valid_theorem(t).unwrap(), matrix.is_valid_theorem(t).unwrap(), "{}", logic.name());
+      }
+    }
+    let premises = (1..=13).map(|i| format!("a{} v b{}", i, i)).collect::<Vec<_>>().join(", ");
+    let e = TableauLogic::FDE.is_valid_theorem(&format!("{} |- x v ~x", premises)).unwrap_err();
+    assert_eq!(e.message(), "the tableau grew too large to decide this");
+    assert!(TableauLogic::LP.is_valid_theorem(&format!("{} |- x v ~x", premises)).unwrap().1, "closes right away");
+  }
+}
@@ -0,0 +1,64 @@
+//! The Routley star semantics for FDE, from section 8.5 of Priest.
+//!
+//! An interpretation has two worlds, `@` and its star `@*`, with sentence letters true or false at each.  Everything
+//! but negation is evaluated as usual at a world, while `¬A` is true at a world when `A` is false at its star.  A
+//! letter is then true in the relational sense when it's true at `@`, and false when it is false at `@*`.
+
+use crate::logic::classical::Expression;
+use crate::logic::formula::Variable;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct StarModel {
+  /// The letters true at `@`.
+  pub actual: BTreeSet<Variable>,
+  /// The letters true at `@*`.
+  pub star: BTreeSet<Variable>,
+}
+
+impl StarModel {
+  /// Reads off a star interpretation from whether each letter is true and whether it is false.
+  pub fn from_relation(relation: &BTreeMap<Variable, (bool, bool)>) -> Self {
+    let actual = relation.iter().filter(|(_, (t, _))| *t).map(|(v, _)| *v).collect();
+    let star = relation.iter().filter(|(_, (_, f))| !*f).map(|(v, _)| *v).collect();
+    Self { actual, star }
+  }
+
+  /// Whether `e` is true at `@`.
+  pub fn is_true(&self, e: &Expression) -> bool {
+    self.eval(e, false)
+  }
+
+  /// Whether `e` is true at `@*` if `starred`, and at `@` otherwise.  `A → B` is `¬A ∨ B`, and the other connectives
+  /// are defined as in the matrices.
+  pub fn eval(&self, e: &Expression, starred: bool) -> bool {
+    use Expression::*;
+    // The value of the negation of `a` at this world.
+    let not = |a: &Expression| !self.eval(a, !starred);
+    let conditional = |a: &Expression, b: &Expression| not(a) || self.eval(b, starred);
+    match e {
+      Variable(v) if starred => self.star.contains(v),
+      Variable(v) => self.actual.contains(v),
+      Top => true,
+      Bottom => false,
+      Negated(a) => not(a),
+      And(a, b) => self.eval(a, starred) && self.eval(b, starred),
+      Or(a, b) => self.eval(a, starred) || self.eval(b, starred),
+      Conditional(a, b) => conditional(a, b),
+      Biconditional(a, b) => conditional(a, b) && conditional(b, a),
+      Nand(a, b) => !(self.eval(a, !starred) && self.eval(b, !starred)),
+      Nor(a, b) => !(self.eval(a, !starred) || self.eval(b, !starred)),
+      Xor(a, b) => !self.eval(&Biconditional(a.clone(), b.clone()), !starred),
+    }
+  }
+}
+
+impl std::fmt::Display for StarModel {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let list = |letters: &BTreeSet<Variable>| {
+      let letters = letters.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+      format!("{{{}}}", letters.join(", "))
+    };
+    write!(f, "true at @: {}, true at @*: {}", list(&self.actual), list(&self.star))
+  }
+}
@@ -0,0 +1,412 @@
+//! Signed tableaux for First Degree Entailment and its extensions K3 and LP, following chapter 8 of Priest.
+//!
+//! Every sentence on a branch is marked `+` if it is true and `−` if it is not.  A sentence may be true and false at
+//! once, or neither, so `A, +` and `¬A, −` don't contradict each other; only `A, +` and `A, −` together close an FDE
+//! branch.  K3 adds that nothing is both true and false, closing on `A, +` and `¬A, +`, and LP that everything is one
+//! or the other, closing on `A, −` and `¬A, −`.
+//!
+//! To test an inference we mark its assumptions `+` and its conclusion `−`.  An open branch describes a relational
+//! countermodel, which can also be read as a Routley star interpretation.
+
+use super::star::StarModel;
+use super::{Assignment, Matrix};
+use crate::logic::classical::{Expression, Theorem};
+use crate::logic::formula::{Operator, Variable};
+use crate::logic::latex::{self, Latex};
+use crate::logic::tableau::{Branching, NodeId, Tree};
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Sign {
+  /// The sentence is true.
+  Plus,
+  /// The sentence is not true.
+  Minus,
+}
+
+impl std::fmt::Display for Sign {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let s = match self {
+      Self::Plus => "+",
+      Self::Minus => "−",
+    };
+    write!(f, "{}", s)
+  }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Signed {
+  pub sentence: Expression,
+  pub sign: Sign,
+}
+
+impl Signed {
+  pub fn new(sentence: Expression, sign: Sign) -> Self {
+    Self { sentence, sign }
+  }
+}
+
+impl std::fmt::Display for Signed {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}, {}", self.sentence, self.sign)
+  }
+}
+
+impl Latex for Signed {
+  fn latex(&self) -> String {
+    let sign = match self.sign {
+      Sign::Plus => "+",
+      Sign::Minus => "-",
+    };
+    format!("{}, {}", self.sentence.latex(), sign)
+  }
+}
+
+/// Which of the three logics a tableau is for, which only changes when its branches close.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Closure {
+  Fde,
+  K3,
+  Lp,
+}
+
+impl Closure {
+  /// The matrix whose values countermodels are given in.
+  pub fn matrix(&self) -> Matrix {
+    match self {
+      Self::Fde => Matrix::fde(),
+      Self::K3 => Matrix::k3(),
+      Self::Lp => Matrix::lp(),
+    }
+  }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Rule {
+  Assumption,
+  Conclusion,
+  /// Decomposing a sentence with the given main operator, or the negation of one if `negated`; `¬¬` is a negated `¬`.
+  Decomposition {
+    negated: bool,
+    operator: Operator,
+    sign: Sign,
+  },
+}
+
+impl Branching for Rule {
+  fn is_branching(&self) -> bool {
+    match self {
+      Self::Assumption | Self::Conclusion => false,
+      Self::Decomposition { negated, operator, sign } => {
+        let branches_when_true = match operator {
+          Operator::And => *negated,
+          Operator::Or | Operator::Conditional => !*negated,
+          _ => return false,
+        };
+        branches_when_true == (*sign == Sign::Plus)
+      }
+    }
+  }
+}
+
+impl std::fmt::Display for Rule {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Assumption => write!(f, "A"),
+      Self::Conclusion => write!(f, "C"),
+      Self::Decomposition { negated, operator, sign } => {
+        write!(f, "{}{}{}", if *negated { "¬" } else { "" }, operator, sign)
+      }
+    }
+  }
+}
+
+impl Latex for Rule {
+  fn latex(&self) -> String {
+    latex::from_unicode(&self.to_string())
+  }
+}
+
+/// What a rule does to a signed sentence: each inner `Vec` is one branch, listing the sentences added to it.  `↔` is
+/// rewritten as `(A → B) ∧ (B → A)`, `↑` as `¬(A ∧ B)`, `↓` as `¬(A ∨ B)` and `⊕` as `¬(A ↔ B)`, just as in the
+/// matrices.
+fn decompose(line: &Signed) -> Option<(Rule, Vec<Vec<Signed>>)> {
+  use Expression::*;
+  let (negated, e) = match &line.sentence {
+    Negated(inner) => (true, inner.as_ref()),
+    e => (false, e),
+  };
+  let neg = |e: Expression| Negated(Box::new(e));
+  let maybe_neg = |negated: bool, e: &Expression| if negated { neg(e.clone()) } else { e.clone() };
+  let boxed = |e: &Expression| Box::new(e.clone());
+  // The parts of the sentence, and whether it is true when they all are rather than when any one is.
+  let (operator, parts, conjunctive) = match e {
+    Variable(_) | Top | Bottom => return None,
+    // Only reached under a negation, since a negated sentence is handled as the negation of what it negates.
+    Negated(a) => (Operator::Not, vec![*a.clone()], true),
+    And(a, b) => (Operator::And, vec![maybe_neg(negated, a), maybe_neg(negated, b)], !negated),
+    Or(a, b) => (Operator::Or, vec![maybe_neg(negated, a), maybe_neg(negated, b)], negated),
+    Conditional(a, b) if negated => (Operator::Conditional, vec![*a.clone(), neg(*b.clone())], true),
+    Conditional(a, b) => (Operator::Conditional, vec![neg(*a.clone()), *b.clone()], false),
+    Biconditional(a, b) => {
+      let definition = And(Box::new(Conditional(boxed(a), boxed(b))), Box::new(Conditional(boxed(b), boxed(a))));
+      (Operator::Biconditional, vec![maybe_neg(negated, &definition)], true)
+    }
+    Nand(a, b) => (Operator::Nand, vec![maybe_neg(!negated, &And(boxed(a), boxed(b)))], true),
+    Nor(a, b) => (Operator::Nor, vec![maybe_neg(!negated, &Or(boxed(a), boxed(b)))], true),
+    Xor(a, b) => (Operator::Xor, vec![maybe_neg(!negated, &Biconditional(boxed(a), boxed(b)))], true),
+  };
+  let sign = line.sign;
+  let rule = Rule::Decomposition { negated, operator, sign };
+  // A sentence is untrue when any part is untrue if it's true only when they all are, and vice versa.
+  let signed = parts.into_iter().map(|p| Signed::new(p, sign));
+  let branches = if conjunctive == (sign == Sign::Plus) {
+    vec![signed.collect()]
+  } else {
+    signed.map(|s| vec![s]).collect()
+  };
+  Some((rule, branches))
+}
+
+#[derive(Debug, Clone)]
+pub struct Tableau {
+  tree: Tree<Signed, Rule>,
+  closure: Closure,
+  complete: bool,
+}
+
+impl Tableau {
+  /// Builds and completes the tableau for the inference from `assumptions` to `conclusion`.
+  pub fn new(assumptions: &[Expression], conclusion: &Expression, closure: Closure) -> Self {
+    let mut tableau = Self {
+      tree: Tree::new(),
+      closure,
+      complete: false,
+    };
+    let mut leaf = None;
+    for a in assumptions {
+      leaf = Some(tableau.tree.push(leaf, Signed::new(a.clone(), Sign::Plus), Rule::Assumption, None));
+    }
+    let conclusion = Signed::new(conclusion.clone(), Sign::Minus);
+    let leaf = tableau.tree.push(leaf, conclusion, Rule::Conclusion, None);
+    let contradictory = |tree: &Tree<Signed, Rule>, leaf| is_contradictory(tree, leaf, closure);
+    if contradictory(&tableau.tree, leaf) {
+      tableau.tree.close(leaf);
+    }
+    tableau.complete = tableau.tree.decompose_all(decompose, contradictory);
+    tableau
+  }
+
+  pub fn for_theorem(thm: &Theorem, closure: Closure) -> Self {
+    Self::new(thm.assumptions(), thm.conclusion(), closure)
+  }
+
+  pub fn tree(&self) -> &Tree<Signed, Rule> {
+    &self.tree
+  }
+
+  pub fn closure(&self) -> Closure {
+    self.closure
+  }
+
+  /// A closed tableau means the inference is valid.
+  pub fn is_closed(&self) -> bool {
+    self.tree.is_closed()
+  }
+
+  /// False if we gave up because the tableau grew too large, in which case an open branch proves nothing.
+  pub fn is_complete(&self) -> bool {
+    self.complete
+  }
+
+  pub fn open_branches(&self) -> Vec<Vec<&Signed>> {
+    self.tree.open_leaves().into_iter().map(|leaf| self.tree.branch_contents(leaf)).collect()
+  }
+
+  /// The values of the sentence letters in the first open branch, if there is one, as whether each is true and
+  /// whether it is false.
+  ///
+  /// In FDE and K3 a letter is true when it is marked `+` and false when its negation is.  Open LP branches can leave
+  /// a letter unsettled, so there it is true unless marked `−` and false unless its negation is.
+  pub fn relation(&self) -> Option<BTreeMap<Variable, (bool, bool)>> {
+    if !self.complete {
+      return None;
+    }
+    let leaf = *self.tree.open_leaves().first()?;
+    let branch = self.tree.branch_contents(leaf);
+    let marked = |e: &Expression, sign: Sign| branch.iter().any(|s| s.sentence == *e && s.sign == sign);
+    let variables = self.tree.nodes().flat_map(|(_, n)| n.content.sentence.variables()).collect::<HashSet<_>>();
+    let relation = variables
+      .into_iter()
+      .map(|v| {
+        let letter = Expression::Variable(v);
+        let negation = Expression::Negated(Box::new(letter.clone()));
+        let value = match self.closure {
+          Closure::Fde | Closure::K3 => (marked(&letter, Sign::Plus), marked(&negation, Sign::Plus)),
+          Closure::Lp => (!marked(&letter, Sign::Minus), !marked(&negation, Sign::Minus)),
+        };
+        (v, value)
+      })
+      .collect();
+    Some(relation)
+  }
+
+  /// The countermodel described by the first open branch, in the values of the tableau's matrix: `b` is both true and
+  /// false and `n` neither, with K3's and LP's `i` standing for whichever of the two the logic allows.
+  pub fn countermodel(&self) -> Option<Assignment> {
+    let matrix = self.closure.matrix();
+    let values = self
+      .relation()?
+      .into_iter()
+      .map(|(v, value)| {
+        let name = match value {
+          (true, false) => "1",
+          (false, true) => "0",
+          (true, true) if self.closure == Closure::Fde => "b",
+          (false, false) if self.closure == Closure::Fde => "n",
+          _ => "i",
+        };
+        (v, matrix.values().iter().position(|n| n == name).unwrap())
+      })
+      .collect();
+    Some(matrix.assignment(values))
+  }
+
+  /// The countermodel described by the first open branch, as a Routley star interpretation.
+  pub fn star_model(&self) -> Option<StarModel> {
+    Some(StarModel::from_relation(&self.relation()?))
+  }
+}
+
+/// Whether a branch closes in the logic `closure` is for.
+fn is_contradictory(tree: &Tree<Signed, Rule>, leaf: NodeId, closure: Closure) -> bool {
+  let branch = tree.branch_contents(leaf);
+  let has = |e: &Expression, sign: Sign| branch.iter().any(|s| s.sentence == *e && s.sign == sign);
+  branch.iter().any(|s| {
+    let negation = Expression::Negated(Box::new(s.sentence.clone()));
+    // `⊤` is just true and `⊥` just false, in every one of the logics.
+    let constant = match (&s.sentence, s.sign) {
+      (Expression::Top, Sign::Minus) | (Expression::Bottom, Sign::Plus) => true,
+      (Expression::Negated(inner), sign) => match inner.as_ref() {
+        Expression::Top => sign == Sign::Plus,
+        Expression::Bottom => sign == Sign::Minus,
+        _ => false,
+      },
+      _ => false,
+    };
+    let gap_or_glut = match (closure, s.sign) {
+      (Closure::K3, Sign::Plus) => has(&negation, Sign::Plus),
+      (Closure::Lp, Sign::Minus) => has(&negation, Sign::Minus),
+      _ => false,
+    };
+    constant || gap_or_glut || (s.sign == Sign::Plus && has(&s.sentence, Sign::Minus))
+  })
+}
+
+impl std::fmt::Display for Tableau {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.tree)
+  }
+}
+
+impl Latex for Tableau {
+  fn latex(&self) -> String {
+    self.tree.latex()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+
+  fn parse(sentences: &[&str]) -> Vec<Expression> {
+    sentences.iter().map(|s| ClassicalParser::parse_expression(s).unwrap()).collect()
+  }
+
+  fn tableau(assumptions: &[&str], conclusion: &str, closure: Closure) -> Tableau {
+    Tableau::new(&parse(assumptions), &parse(&[conclusion])[0], closure)
+  }
+
+  #[test]
+  fn test_fde() {
+    let closed = |a: &[&str], c| tableau(a, c, Closure::Fde).is_closed();
+    assert!(closed(&["p ^ q"], "p"));
+    assert!(closed(&["p"], "p v q"));
+    assert!(closed(&["~(p ^ q)"], "~p v ~q"));
+    assert!(closed(&["~~p"], "p"));
+    assert!(closed(&["~(p -> q)"], "p"));
+    assert!(closed(&[], "T"));
+    assert!(closed(&["F"], "p"));
+    assert!(!closed(&[], "p v ~p"));
+    assert!(!closed(&["p", "~p"], "q"));
+    assert!(!closed(&["p", "p -> q"], "q"));
+    assert!(!closed(&["p ^ ~p"], "q v ~q"));
+  }
+
+  #[test]
+  fn test_k3_and_lp() {
+    assert!(tableau(&["p", "~p"], "q", Closure::K3).is_closed());
+    assert!(tableau(&["p", "p -> q"], "q", Closure::K3).is_closed());
+    assert!(!tableau(&[], "p v ~p", Closure::K3).is_closed());
+    assert!(tableau(&[], "p v ~p", Closure::Lp).is_closed());
+    assert!(tableau(&[], "p -> p", Closure::Lp).is_closed());
+    assert!(!tableau(&["p", "~p"], "q", Closure::Lp).is_closed());
+    assert!(!tableau(&["p", "p -> q"], "q", Closure::Lp).is_closed());
+  }
+
+  #[test]
+  fn test_rules() {
+    let t = tableau(&["p ^ q"], "p v r", Closure::Fde);
+    let tree = t.tree();
+    assert_eq!(tree.node(1).content.to_string(), "p ∨ r, −");
+    assert_eq!(tree.node(2).rule.to_string(), "∧+");
+    assert_eq!(tree.node(4).rule.to_string(), "∨−");
+    assert!(t.is_closed());
+
+    let t = tableau(&["~(p ^ q)"], "p", Closure::Fde);
+    assert_eq!(t.tree().leaves().len(), 2);
+    assert_eq!(t.tree().node(2).rule.to_string(), "¬∧+");
+  }
+
+  #[test]
+  fn test_agrees_with_matrices() {
+    let inferences: &[(&[&str], &str)] = &[
+      (&[], "p v ~p"),
+      (&["p", "~p"], "q"),
+      (&["p", "p -> q"], "q"),
+      (&["p -> q", "~q"], "~p"),
+      (&["p <-> q"], "q <-> p"),
+      (&["p xor q"], "~(p <-> q)"),
+      (&["p nand q"], "~p v ~q"),
+      (&["~(p nor q)", "~p"], "q"),
+      (&["p ^ ~p"], "q v ~q"),
+      (&["~(p -> F)"], "p"),
+    ];
+    for closure in [Closure::Fde, Closure::K3, Closure::Lp].iter() {
+      let matrix = closure.matrix();
+      for (assumptions, conclusion) in inferences {
+        let (assumptions, conclusion) = (parse(assumptions), parse(&[conclusion])[0].clone());
+        let t = Tableau::new(&assumptions, &conclusion, *closure);
+        assert_eq!(t.is_closed(), matrix.is_valid(&assumptions, &conclusion), "{:?} {}", closure, conclusion);
+        if let Some(countermodel) = t.countermodel() {
+          assert!(matrix.is_countermodel(&assumptions, &conclusion, &countermodel), "{}", countermodel);
+          let star = t.star_model().unwrap();
+          assert!(assumptions.iter().all(|a| star.is_true(a)));
+          assert!(!star.is_true(&conclusion), "{}", star);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_countermodels() {
+    let t = tableau(&["p", "~p"], "q", Closure::Fde);
+    assert_eq!(t.countermodel().unwrap().to_string(), "v(p) = b, v(q) = n");
+    let t = tableau(&["p", "~p"], "q", Closure::Lp);
+    assert_eq!(t.countermodel().unwrap().to_string(), "v(p) = i, v(q) = 0");
+    let t = tableau(&[], "p v ~p", Closure::K3);
+    assert_eq!(t.countermodel().unwrap().to_string(), "v(p) = i");
+    assert_eq!(tableau(&["p"], "p", Closure::Fde).countermodel(), None);
+  }
+}
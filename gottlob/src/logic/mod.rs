@@ -1,14 +1,163 @@
 pub mod classical;
+pub mod formula;
+pub mod intuitionistic;
+pub mod latex;
+pub mod many_valued;
 pub mod modal;
+pub mod parser;
+pub mod printer;
+pub mod simplify;
+pub mod tableau;
+
+use formula::{Language, Operator};
+use std::ops::Range;
 
 pub type ParsedSentence = String;
 
-// TODO: proper enum with good info about the error.
 #[derive(Debug, Eq, PartialEq, Clone)]
-pub struct ParseError;
+pub enum ParseError {
+  /// The input stopped matching the grammar at `span`, where one of `expected` would have been fine.
+  Unexpected {
+    input: String,
+    span: Range<usize>,
+    expected: Vec<&'static str>,
+  },
+  /// The input uses an operator, at `span`, that isn't part of the language being parsed.
+  Unsupported {
+    input: String,
+    span: Range<usize>,
+    operator: Operator,
+    language: Language,
+  },
+  /// Some other problem with the input at `span`, such as it being too large for us to decide.
+  Invalid {
+    input: String,
+    span: Range<usize>,
+    message: String,
+  },
+}
+
 pub type LogicResult = Result<(ParsedSentence, bool), ParseError>;
 
 pub trait Logic {
   fn name(&self) -> &'static str;
   fn is_valid_theorem(&self, s: &str) -> LogicResult;
-}
\ No newline at end of file
+}
+
+/// Describes a grammar rule the way we'd describe it to a user, e.g. "a sentence letter".
+pub trait RuleDescription {
+  fn description(&self) -> &'static str;
+}
+
+impl ParseError {
+  pub fn from_pest<R: pest::RuleType + RuleDescription>(input: &str, e: pest::error::Error<R>) -> Self {
+    use pest::error::{ErrorVariant, InputLocation};
+    let span = match e.location {
+      InputLocation::Pos(p) => p..p,
+      InputLocation::Span((start, end)) => start..end,
+    };
+    let input = input.to_owned();
+    match e.variant {
+      ErrorVariant::ParsingError { positives, .. } => {
+        let mut expected = Vec::new();
+        for description in positives.iter().map(|r| r.description()) {
+          if !expected.contains(&description) {
+            expected.push(description);
+          }
+        }
+        Self::Unexpected { input, span, expected }
+      }
+      ErrorVariant::CustomError { message } => Self::Invalid { input, span, message },
+    }
+  }
+
+  /// For input that parses but that we gave up on deciding, e.g. because its tableau grew past `tableau::MAX_NODES`.
+  pub fn undecided(input: &str, message: &str) -> Self {
+    Self::Invalid {
+      input: input.to_owned(),
+      span: 0..input.len(),
+      message: message.to_owned(),
+    }
+  }
+
+  pub fn input(&self) -> &str {
+    match self {
+      Self::Unexpected { input, .. } | Self::Unsupported { input, .. } | Self::Invalid { input, .. } => input,
+    }
+  }
+
+  /// The byte range of the input the error is about.  It may be empty, e.g. when something is missing at the end.
+  pub fn span(&self) -> Range<usize> {
+    match self {
+      Self::Unexpected { span, .. } | Self::Unsupported { span, .. } | Self::Invalid { span, .. } => span.clone(),
+    }
+  }
+
+  pub fn message(&self) -> String {
+    match self {
+      Self::Unexpected { input, span, expected } => {
+        let found = match input[span.start..].chars().next() {
+          Some(c) => format!("'{}'", c),
+          None => "the end of the input".to_owned(),
+        };
+        match expected.split_last() {
+          None => format!("unexpected {}", found),
+          Some((last, [])) => format!("expected {}, found {}", last, found),
+          Some((last, rest)) => format!("expected {} or {}, found {}", rest.join(", "), last, found),
+        }
+      }
+      Self::Unsupported { operator, language, .. } => format!("'{}' isn't part of {}", operator, language.name()),
+      Self::Invalid { message, .. } => message.clone(),
+    }
+  }
+}
+
+/// Shows the message followed by the offending line of input with a caret under the problem.
+impl std::fmt::Display for ParseError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let input = self.input();
+    let span = self.span();
+    let line_start = input[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = input[span.start..].find('\n').map_or(input.len(), |i| span.start + i);
+    let line = input[line_start..line_end].trim_end_matches('\r');
+    let column = input[line_start..span.start].chars().count();
+    let width = input[span.start..span.end.min(line_end)].chars().count().max(1);
+    writeln!(f, "{}", self.message())?;
+    writeln!(f, "{}", line)?;
+    write!(f, "{}{}", " ".repeat(column), "^".repeat(width))
+  }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+  use crate::logic::modal::parser::ModalParser;
+
+  #[test]
+  fn test_parse_error_expected() {
+    let e = ClassicalParser::parse_expression("(p ^ q").unwrap_err();
+    assert_eq!(e.span(), 6..6);
+    match &e {
+      ParseError::Unexpected { expected, .. } => assert!(expected.contains(&"a closing parenthesis")),
+      _ => panic!("expected an unexpected input error"),
+    }
+
+    let e = ModalParser::parse_theorem("p, |- q").unwrap_err();
+    assert_eq!(e.span(), 3..3);
+    assert_eq!(e.message(), "expected a sentence, found '|'");
+  }
+
+  #[test]
+  fn test_parse_error_display() {
+    let e = ClassicalParser::parse_expression("p ^ ^ q").unwrap_err();
+    assert_eq!(format!("{}", e), "expected a sentence, found '^'\np ^ ^ q\n    ^");
+    let e = ModalParser::parse_expression("[]p -> ").unwrap_err();
+    assert_eq!(format!("{}", e), "expected a sentence, found the end of the input\n[]p -> \n       ^");
+
+    let e = ClassicalParser::parse_expression("p ^ <>q").unwrap_err();
+    assert_eq!(format!("{}", e), "'◇' isn't part of classical logic\np ^ <>q\n    ^^^");
+  }
+}
@@ -0,0 +1,22 @@
+use super::kripke::KripkeModel;
+use super::Expression;
+
+/// A Kripke interpretation in which the assumptions of an inference are true at world 0 and its conclusion is false
+/// there.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct Countermodel {
+  pub model: KripkeModel,
+}
+
+impl Countermodel {
+  /// Checks that this really is a countermodel by evaluating the inference at world 0.
+  pub fn is_countermodel(&self, assumptions: &[Expression], conclusion: &Expression) -> bool {
+    assumptions.iter().all(|a| a.eval_at(&self.model, 0)) && !conclusion.eval_at(&self.model, 0)
+  }
+}
+
+impl std::fmt::Display for Countermodel {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.model)
+  }
+}
@@ -0,0 +1,289 @@
+use super::{Agent, FrameConditions, Index, Variable};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub type World = usize;
+
+/// A Kripke interpretation: some worlds, which worlds can see which, and the sentence letters true at each world.
+/// Letters not listed for a world are false there.
+///
+/// Indexed operators such as `K_a` each have a relation of their own in `indexed`.  `agents` are who `E` and `C` are
+/// about; everyone with a `K_a` or `B_a` relation is one.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct KripkeModel {
+  pub worlds: BTreeSet<World>,
+  pub accessible: BTreeSet<(World, World)>,
+  pub indexed: BTreeMap<Index, BTreeSet<(World, World)>>,
+  pub agents: BTreeSet<Agent>,
+  pub valuation: BTreeMap<World, BTreeSet<Variable>>,
+}
+
+impl KripkeModel {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn add_world(&mut self, w: World) {
+    self.worlds.insert(w);
+  }
+
+  /// Makes `v` accessible from `w`, adding either world if it isn't there yet.
+  pub fn add_access(&mut self, w: World, v: World) {
+    self.worlds.insert(w);
+    self.worlds.insert(v);
+    self.accessible.insert((w, v));
+  }
+
+  /// Makes `v` accessible from `w` along the relation of `index`.
+  pub fn add_indexed_access(&mut self, index: Index, w: World, v: World) {
+    self.worlds.insert(w);
+    self.worlds.insert(v);
+    self.indexed.entry(index).or_default().insert((w, v));
+    if let Index::Knowledge(a) | Index::Belief(a) = index {
+      self.agents.insert(a);
+    }
+  }
+
+  pub fn set_true(&mut self, w: World, v: Variable) {
+    self.worlds.insert(w);
+    self.valuation.entry(w).or_default().insert(v);
+  }
+
+  pub fn is_true(&self, w: World, v: Variable) -> bool {
+    self.valuation.get(&w).is_some_and(|trues| trues.contains(&v))
+  }
+
+  pub fn accessible_from(&self, w: World) -> impl Iterator<Item = World> + '_ {
+    self.accessible.iter().filter(move |(from, _)| *from == w).map(|(_, v)| *v)
+  }
+
+  /// The worlds that can see `w`: read as times, those earlier than it.
+  pub fn accessible_to(&self, w: World) -> impl Iterator<Item = World> + '_ {
+    self.accessible.iter().filter(move |(_, to)| *to == w).map(|(v, _)| *v)
+  }
+
+  /// The worlds `w` sees along the relation of `index`.
+  pub fn accessible_by(&self, index: Index, w: World) -> impl Iterator<Item = World> + '_ {
+    self.indexed.get(&index).into_iter().flatten().filter(move |(from, _)| *from == w).map(|(_, v)| *v)
+  }
+
+  /// The worlds reachable from `w` in one or more steps along what any of the agents knows: those that have to make
+  /// a sentence true for it to be common knowledge at `w`.
+  pub fn known_reachable(&self, w: World) -> BTreeSet<World> {
+    let mut reachable = BTreeSet::new();
+    let mut todo = vec![w];
+    while let Some(v) = todo.pop() {
+      for a in &self.agents {
+        for u in self.accessible_by(Index::Knowledge(*a), v) {
+          if reachable.insert(u) {
+            todo.push(u);
+          }
+        }
+      }
+    }
+    reachable
+  }
+
+  pub fn satisfies(&self, conditions: FrameConditions) -> bool {
+    satisfies(&self.worlds, &self.accessible, conditions)
+  }
+
+  /// Whether the relation of `index` meets `conditions`.
+  pub fn indexed_satisfies(&self, index: Index, conditions: FrameConditions) -> bool {
+    satisfies(&self.worlds, self.indexed.get(&index).unwrap_or(&BTreeSet::new()), conditions)
+  }
+
+  /// Adds whatever accessibility facts are needed for the relation to be reflexive, symmetric, transitive or euclidean
+  /// as `conditions` requires.  Seriality, unbounded pasts and density can't be had just by adding facts between
+  /// existing worlds, and there's no one way to make a relation linear, so those are left alone.
+  pub fn close_under(&mut self, conditions: FrameConditions) {
+    close_under(&self.worlds, &mut self.accessible, conditions)
+  }
+
+  /// Like `close_under`, for the relation of `index`.
+  pub fn close_indexed_under(&mut self, index: Index, conditions: FrameConditions) {
+    close_under(&self.worlds, self.indexed.entry(index).or_default(), conditions)
+  }
+}
+
+fn satisfies(worlds: &BTreeSet<World>, accessible: &BTreeSet<(World, World)>, conditions: FrameConditions) -> bool {
+  let r = |w: &World, v: &World| accessible.contains(&(*w, *v));
+  let pairs = || accessible.iter();
+  let from = |w: World| pairs().filter(move |(v, _)| *v == w).map(|(_, u)| *u);
+  let to = |w: World| pairs().filter(move |(_, v)| *v == w).map(|(u, _)| *u);
+  (!conditions.serial || worlds.iter().all(|w| from(*w).next().is_some()))
+    && (!conditions.reflexive || worlds.iter().all(|w| r(w, w)))
+    && (!conditions.symmetric || pairs().all(|(w, v)| r(v, w)))
+    && (!conditions.transitive || pairs().all(|(w, v)| from(*v).all(|u| r(w, &u))))
+    && (!conditions.euclidean || pairs().all(|(w, v)| from(*w).all(|u| r(v, &u))))
+    && (!conditions.unbounded_past || worlds.iter().all(|w| to(*w).next().is_some()))
+    && (!conditions.dense || pairs().all(|(w, v)| from(*w).any(|u| r(&u, v))))
+    && (!conditions.linear
+      || pairs().all(|(w, v)| {
+        // Nothing branches off after `w` or before `v`.
+        let related = |x: &World, u: World| u == *x || r(x, &u) || r(&u, x);
+        from(*w).all(|u| related(v, u)) && to(*v).all(|u| related(w, u))
+      }))
+}
+
+fn close_under(worlds: &BTreeSet<World>, accessible: &mut BTreeSet<(World, World)>, conditions: FrameConditions) {
+  loop {
+    let mut missing = BTreeSet::new();
+    for w in worlds {
+      if conditions.reflexive {
+        missing.insert((*w, *w));
+      }
+    }
+    for (w, v) in accessible.iter() {
+      if conditions.symmetric {
+        missing.insert((*v, *w));
+      }
+      for (x, u) in accessible.iter() {
+        if conditions.transitive && v == x {
+          missing.insert((*w, *u));
+        }
+        if conditions.euclidean && w == x {
+          missing.insert((*v, *u));
+        }
+      }
+    }
+    let before = accessible.len();
+    accessible.extend(missing);
+    if accessible.len() == before {
+      return;
+    }
+  }
+}
+
+impl std::fmt::Display for KripkeModel {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let worlds = self.worlds.iter().map(|w| w.to_string()).collect::<Vec<_>>().join(", ");
+    let accessible = self
+      .accessible
+      .iter()
+      .map(|(w, v)| format!("{}r{}", w, v))
+      .collect::<Vec<_>>()
+      .join(", ");
+    writeln!(f, "W = {{{}}}", worlds)?;
+    write!(f, "R = {{{}}}", accessible)?;
+    for (index, accessible) in &self.indexed {
+      let accessible = accessible.iter().map(|(w, v)| format!("{}r{}", w, v)).collect::<Vec<_>>().join(", ");
+      write!(f, "\nR[{}] = {{{}}}", index, accessible)?;
+    }
+    for w in &self.worlds {
+      let trues = self
+        .valuation
+        .get(w)
+        .map(|trues| trues.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "))
+        .unwrap_or_default();
+      write!(f, "\n{} ⊩ {{{}}}", w, trues)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::modal::parser::ModalParser;
+  use crate::logic::modal::Agent;
+
+  fn eval_at(model: &KripkeModel, s: &str, w: World) -> bool {
+    ModalParser::parse_expression(s).unwrap().eval_at(model, w)
+  }
+
+  #[test]
+  fn test_eval_at() {
+    // 0 sees 1 and 2; p is true at 1 only, q at 1 and 2.
+    let mut model = KripkeModel::new();
+    model.add_access(0, 1);
+    model.add_access(0, 2);
+    model.set_true(1, Variable::new("p"));
+    model.set_true(1, Variable::new("q"));
+    model.set_true(2, Variable::new("q"));
+
+    assert!(!eval_at(&model, "p", 0));
+    assert!(eval_at(&model, "<>p", 0));
+    assert!(!eval_at(&model, "[]p", 0));
+    assert!(eval_at(&model, "[]q", 0));
+    assert!(eval_at(&model, "<>(p ^ q) ^ <>(~p ^ q)", 0));
+    assert!(eval_at(&model, "[]~<>p", 0), "nothing is accessible from 1 or 2");
+    assert!(eval_at(&model, "[]p", 1), "boxes are vacuously true at dead ends");
+    assert!(!eval_at(&model, "<>(p v ~p)", 2));
+  }
+
+  #[test]
+  fn test_indexed_and_common_knowledge() {
+    // a can't tell 0 from 1, and b can't tell 1 from 2; p is false only at 2.
+    let (a, b) = (Index::Knowledge(Agent::new("a")), Index::Knowledge(Agent::new("b")));
+    let mut model = KripkeModel::new();
+    model.add_indexed_access(a, 0, 1);
+    model.add_indexed_access(b, 1, 2);
+    model.close_indexed_under(a, FrameConditions::S5);
+    model.close_indexed_under(b, FrameConditions::S5);
+    model.set_true(0, Variable::new("p"));
+    model.set_true(1, Variable::new("p"));
+    assert!(model.indexed_satisfies(a, FrameConditions::S5));
+    assert!(!model.indexed_satisfies(Index::Belief(Agent::new("a")), FrameConditions::KD45), "a believes nothing");
+
+    assert!(eval_at(&model, "K_a p", 0));
+    assert!(!eval_at(&model, "K_b p", 1));
+    assert!(eval_at(&model, "E p", 0));
+    assert!(!eval_at(&model, "E E p", 0));
+    assert!(!eval_at(&model, "C p", 0));
+    assert!(eval_at(&model, "C (p v ~p)", 0));
+    assert!(eval_at(&model, "<K_b>~p", 1));
+    assert!(eval_at(&model, "[c]F", 0), "nothing is accessible along c");
+    assert_eq!(model.known_reachable(0).into_iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+    assert!(model.to_string().contains("R[K_b] = {0r0, 1r1, 1r2, 2r1, 2r2}"), "{}", model);
+  }
+
+  #[test]
+  fn test_frame_conditions() {
+    use FrameConditions as F;
+    let mut model = KripkeModel::new();
+    model.add_access(0, 1);
+    model.add_access(1, 2);
+    assert!(model.satisfies(F::K));
+    assert!(!model.satisfies(F::D));
+
+    model.close_under(F::S4);
+    assert!(model.satisfies(F::S4));
+    assert!(!model.satisfies(F::B));
+    assert!(model.accessible.contains(&(0, 2)));
+
+    model.close_under(F::S5);
+    assert!(model.satisfies(F::S5));
+    assert!(model.satisfies(F::B));
+    assert_eq!(model.accessible.len(), 9);
+  }
+
+  #[test]
+  fn test_linear_frames() {
+    use FrameConditions as F;
+    let mut model = KripkeModel::new();
+    model.add_access(0, 1);
+    model.add_access(1, 2);
+    model.close_under(F::S4);
+    assert!(model.satisfies(F::KT43));
+
+    // A past that branches: 1 and 2 both come before 0 but are unrelated.
+    let mut model = KripkeModel::new();
+    model.add_access(1, 0);
+    model.add_access(2, 0);
+    model.close_under(F::S4);
+    assert!(!model.satisfies(F::KT43));
+    model.add_access(1, 2);
+    assert!(model.satisfies(F::KT43));
+  }
+
+  #[test]
+  fn test_t_axiom_needs_reflexivity() {
+    let mut model = KripkeModel::new();
+    model.add_access(0, 1);
+    model.set_true(1, Variable::new("p"));
+    assert!(!eval_at(&model, "[]p -> p", 0));
+    model.close_under(FrameConditions::T);
+    assert!(eval_at(&model, "[]p -> p", 0));
+    assert!(eval_at(&model, "[]p -> p", 1));
+  }
+}
@@ -1,133 +1,390 @@
-use log::*;
-use strum_macros::*;
-
+pub mod countermodel;
+pub mod kripke;
 pub mod parser;
+pub mod tableau;
 
+use kripke::{KripkeModel, World};
 use parser::ModalParser;
-use super::{Logic, LogicResult, ParseError};
+use tableau::Tableau;
+use super::formula::{self, Formula, Language, Operator, Shape};
+use super::latex::Latex;
+use super::printer::{self, Style};
+use super::simplify::{self, Simplification};
+use super::{classical, Logic, LogicResult, ParseError};
+use std::collections::BTreeSet;
+
+pub use super::formula::{Agent, Index, Variable};
+
+pub type Theorem = formula::Theorem<Expression>;
+
+/// Restrictions on the accessibility relation of the frames a modal logic is evaluated over.  Any combination is
+/// allowed; the associated constants are the usual named systems.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default, Hash)]
+pub struct FrameConditions {
+  /// Every world sees some world (η in Priest).
+  pub serial: bool,
+  /// Every world sees itself (ρ).
+  pub reflexive: bool,
+  /// If `w` sees `v`, `v` sees `w` (σ).
+  pub symmetric: bool,
+  /// If `w` sees `v` and `v` sees `u`, `w` sees `u` (τ).
+  pub transitive: bool,
+  /// If `w` sees both `v` and `u`, `v` sees `u`.
+  pub euclidean: bool,
+  /// Every world is seen by some world: read as times, there is no first moment.
+  pub unbounded_past: bool,
+  /// Between any world and a world it sees there is another, seen by the first and seeing the second.
+  pub dense: bool,
+  /// Any two worlds seen by the same world, or seeing the same world, are the same or one sees the other: time doesn't
+  /// branch into the future or the past.
+  pub linear: bool,
+}
+
+impl FrameConditions {
+  pub const K: Self = Self {
+    serial: false,
+    reflexive: false,
+    symmetric: false,
+    transitive: false,
+    euclidean: false,
+    unbounded_past: false,
+    dense: false,
+    linear: false,
+  };
+  pub const D: Self = Self { serial: true, ..Self::K };
+  pub const T: Self = Self { reflexive: true, ..Self::K };
+  pub const B: Self = Self { symmetric: true, ..Self::T };
+  pub const S4: Self = Self { transitive: true, ..Self::T };
+  pub const S5: Self = Self { euclidean: true, ..Self::T };
+  /// The minimal tense logic, read over the same frames as K.
+  pub const KT: Self = Self::K;
+  /// Time that runs one way: whatever is later than something later is later still.
+  pub const KT4: Self = Self { transitive: true, ..Self::K };
+  /// Linear time.
+  pub const KT43: Self = Self { linear: true, ..Self::KT4 };
+  /// Dense linear time without a first or last moment, like the rational numbers.
+  pub const Q: Self = Self {
+    serial: true,
+    unbounded_past: true,
+    dense: true,
+    ..Self::KT43
+  };
+
+  /// The usual logic of belief: consistent, and knowing what you do and don't believe.
+  pub const KD45: Self = Self {
+    serial: true,
+    transitive: true,
+    euclidean: true,
+    ..Self::K
+  };
 
-pub struct ModalLogic;
+  /// The conditions on the relation of `index`, when `self` are those on the relation of `◻`.  Knowledge is S5 and
+  /// belief KD45 whatever the system, while a named index gets the system's own conditions, bar those about time.
+  pub fn for_index(self, index: Index) -> Self {
+    match index {
+      Index::Knowledge(_) => Self::S5,
+      Index::Belief(_) => Self::KD45,
+      Index::Named(_) => Self {
+        unbounded_past: false,
+        dense: false,
+        linear: false,
+        ..self
+      },
+    }
+  }
+
+  /// The conditions that hold when both `self` and `other` do.
+  pub fn union(self, other: Self) -> Self {
+    Self {
+      serial: self.serial || other.serial,
+      reflexive: self.reflexive || other.reflexive,
+      symmetric: self.symmetric || other.symmetric,
+      transitive: self.transitive || other.transitive,
+      euclidean: self.euclidean || other.euclidean,
+      unbounded_past: self.unbounded_past || other.unbounded_past,
+      dense: self.dense || other.dense,
+      linear: self.linear || other.linear,
+    }
+  }
+}
+
+/// A normal modal logic, determined by the conditions on its frames.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct ModalLogic {
+  name: &'static str,
+  frame_conditions: FrameConditions,
+}
+
+impl ModalLogic {
+  pub const K: Self = Self::new("K", FrameConditions::K);
+  pub const D: Self = Self::new("D", FrameConditions::D);
+  pub const T: Self = Self::new("T", FrameConditions::T);
+  pub const B: Self = Self::new("B", FrameConditions::B);
+  pub const S4: Self = Self::new("S4", FrameConditions::S4);
+  pub const S5: Self = Self::new("S5", FrameConditions::S5);
+  pub const KT: Self = Self::new("Kt", FrameConditions::KT);
+  pub const KT4: Self = Self::new("Kt4", FrameConditions::KT4);
+  pub const KT43: Self = Self::new("Kt4.3", FrameConditions::KT43);
+  pub const Q: Self = Self::new("Q", FrameConditions::Q);
+
+  /// The named systems, weakest first.
+  pub const SYSTEMS: [Self; 6] = [Self::K, Self::D, Self::T, Self::B, Self::S4, Self::S5];
+  /// The named tense logics, weakest first.
+  pub const TENSE_SYSTEMS: [Self; 4] = [Self::KT, Self::KT4, Self::KT43, Self::Q];
+
+  pub const fn new(name: &'static str, frame_conditions: FrameConditions) -> Self {
+    Self { name, frame_conditions }
+  }
+
+  pub fn frame_conditions(&self) -> FrameConditions {
+    self.frame_conditions
+  }
+}
 
 impl Logic for ModalLogic {
   fn name(&self) -> &'static str {
-    "Modal Logic"
+    self.name
   }
 
   fn is_valid_theorem(&self, t: &str) -> LogicResult {
-    let thm = ModalParser::parse_theorem(t).map_err(|e| {
-      error!("parse error: {:?}", e);
-      ParseError
-    })?;
-    // TODO: actually prove validity
-    Ok((format!("{}", thm), true))
+    let thm = parse(t)?;
+    let tableau = Tableau::for_theorem(&thm, self.frame_conditions);
+    match check(&thm, &tableau) {
+      Some(holds) => Ok((format!("{}", thm), holds)),
+      None if !tableau.is_complete() => Err(ParseError::undecided(t, "the tableau grew too large to decide this")),
+      None => Err(ParseError::undecided(t, "the tableau's open branch doesn't describe a countermodel")),
+    }
   }
 }
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
-pub struct Variable(char);
+/// Decides whether a theorem's claim is correct, given the tableau for it, or `None` if the tableau can't tell: either
+/// it was given up on, or the countermodel read off it isn't one, or isn't over a frame of the system.  Blocking can
+/// stop a branch before it has everything linear, dense or serial frames need, and reading off the interpretation only
+/// adds what reflexive, symmetric, transitive and euclidean frames do.
+pub fn check(thm: &Theorem, tableau: &Tableau) -> Option<bool> {
+  if tableau.is_closed() {
+    return Some(thm.holds(true));
+  }
+  let conditions = tableau.frame_conditions();
+  let countermodel = tableau.countermodel()?;
+  let model = &countermodel.model;
+  let in_system = model.satisfies(conditions)
+    && model.indexed.keys().all(|x| model.indexed_satisfies(*x, conditions.for_index(*x)));
+  if !in_system || !countermodel.is_countermodel(thm.assumptions(), thm.conclusion()) {
+    return None;
+  }
+  Some(thm.holds(false))
+}
 
-impl std::fmt::Display for Variable {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    let Variable(v) = self;
-    write!(f, "{}", v)
+/// Parses a theorem, or a lone sentence as the claim that it is valid.  `E` and `C` are about the agents the inference
+/// names with `K_a` or `B_a`, so they aren't allowed without one.
+pub fn parse(t: &str) -> Result<Theorem, ParseError> {
+  let thm = if t.contains("|-") || t.contains("|/-") {
+    ModalParser::parse_theorem(t)?
+  } else {
+    Theorem::Proves {
+      assumptions: Vec::new(),
+      conclusion: ModalParser::parse_expression(t)?,
+    }
+  };
+  let sentences = || thm.assumptions().iter().chain(Some(thm.conclusion()));
+  let about_everyone = sentences()
+    .flat_map(|e| e.operators())
+    .any(|op| matches!(op, Operator::EverybodyKnows | Operator::CommonKnowledge));
+  if about_everyone && sentences().all(|e| e.agents().is_empty()) {
+    return Err(ParseError::Invalid {
+      input: t.to_owned(),
+      span: 0..t.len(),
+      message: "E and C need at least one agent, named by a K_a or B_a".to_owned(),
+    });
   }
+  Ok(thm)
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, EnumDiscriminants)] // Maybe we don't want to derive Eq for these, but instead impl something.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)] // Maybe we don't want to derive Eq for these, but instead impl something.
 pub enum Expression {
   Variable(Variable),
+  Top,
+  Bottom,
   Negated(Box<Expression>),
   And(Box<Expression>, Box<Expression>),
   Or(Box<Expression>, Box<Expression>),
   Conditional(Box<Expression>, Box<Expression>),
   Biconditional(Box<Expression>, Box<Expression>),
+  Nand(Box<Expression>, Box<Expression>),
+  Nor(Box<Expression>, Box<Expression>),
+  Xor(Box<Expression>, Box<Expression>),
   Necessary(Box<Expression>),
   Possible(Box<Expression>),
+  FutureAlways(Box<Expression>),
+  FutureSometime(Box<Expression>),
+  PastAlways(Box<Expression>),
+  PastSometime(Box<Expression>),
+  IndexedNecessary(Index, Box<Expression>),
+  IndexedPossible(Index, Box<Expression>),
+  EverybodyKnows(Box<Expression>),
+  CommonKnowledge(Box<Expression>),
 }
 
 impl Expression {
-  // TODO: consider being more DRY with these flattening functions
-  fn flatten_and(&self) -> Vec<&Self> {
+  /// Whether this is true at `world` in `model`.  Read as times, the worlds `world` can see are later and those that
+  /// can see it earlier.  Everybody knows something when each of the model's agents does.
+  pub fn eval_at(&self, model: &KripkeModel, world: World) -> bool {
     match self {
-      Expression::And(e1, e2) => [e1.flatten_and(), e2.flatten_and()].concat(),
-      e => vec![e],
+      Self::Variable(v) => model.is_true(world, *v),
+      Self::Top => true,
+      Self::Bottom => false,
+      Self::Negated(e) => !e.eval_at(model, world),
+      Self::And(e1, e2) => e1.eval_at(model, world) && e2.eval_at(model, world),
+      Self::Or(e1, e2) => e1.eval_at(model, world) || e2.eval_at(model, world),
+      Self::Conditional(e1, e2) => !e1.eval_at(model, world) || e2.eval_at(model, world),
+      Self::Biconditional(e1, e2) => e1.eval_at(model, world) == e2.eval_at(model, world),
+      Self::Nand(e1, e2) => !(e1.eval_at(model, world) && e2.eval_at(model, world)),
+      Self::Nor(e1, e2) => !(e1.eval_at(model, world) || e2.eval_at(model, world)),
+      Self::Xor(e1, e2) => e1.eval_at(model, world) != e2.eval_at(model, world),
+      Self::Necessary(e) => model.accessible_from(world).all(|w| e.eval_at(model, w)),
+      Self::Possible(e) => model.accessible_from(world).any(|w| e.eval_at(model, w)),
+      Self::FutureAlways(e) => model.accessible_from(world).all(|w| e.eval_at(model, w)),
+      Self::FutureSometime(e) => model.accessible_from(world).any(|w| e.eval_at(model, w)),
+      Self::PastAlways(e) => model.accessible_to(world).all(|w| e.eval_at(model, w)),
+      Self::PastSometime(e) => model.accessible_to(world).any(|w| e.eval_at(model, w)),
+      Self::IndexedNecessary(i, e) => model.accessible_by(*i, world).all(|w| e.eval_at(model, w)),
+      Self::IndexedPossible(i, e) => model.accessible_by(*i, world).any(|w| e.eval_at(model, w)),
+      Self::EverybodyKnows(e) => {
+        let knows = |a: &Agent| model.accessible_by(Index::Knowledge(*a), world).all(|w| e.eval_at(model, w));
+        model.agents.iter().all(knows)
+      }
+      Self::CommonKnowledge(e) => model.known_reachable(world).into_iter().all(|w| e.eval_at(model, w)),
     }
   }
 
-  fn flatten_or(&self) -> Vec<&Self> {
+  /// The indices of the indexed operators in this sentence.
+  pub fn indices(&self) -> BTreeSet<Index> {
+    let indices = self.operators().into_iter().filter_map(|op| match op {
+      Operator::IndexedNecessary(i) | Operator::IndexedPossible(i) => Some(i),
+      _ => None,
+    });
+    indices.collect()
+  }
+
+  /// Everyone with a `K_a` or `B_a` in this sentence.
+  pub fn agents(&self) -> BTreeSet<Agent> {
+    self.indices().into_iter().filter(|x| !matches!(x, Index::Named(_))).map(|x| x.agent()).collect()
+  }
+
+  /// An equivalent sentence made with the rewrites in `simplify::RewriteRule`, together with each rewrite made.
+  pub fn simplify(&self) -> Simplification<Self> {
+    simplify::simplify(self)
+  }
+}
+
+impl Formula for Expression {
+  fn language() -> Language {
+    Language::Modal
+  }
+
+  fn shape(&self) -> Shape<&Self> {
     match self {
-      Expression::Or(e1, e2) => [e1.flatten_or(), e2.flatten_or()].concat(),
-      e => vec![e],
+      Self::Variable(v) => Shape::Variable(*v),
+      Self::Top => Shape::Constant(true),
+      Self::Bottom => Shape::Constant(false),
+      Self::Negated(e) => Shape::Unary(Operator::Not, e),
+      Self::Necessary(e) => Shape::Unary(Operator::Necessary, e),
+      Self::Possible(e) => Shape::Unary(Operator::Possible, e),
+      Self::FutureAlways(e) => Shape::Unary(Operator::FutureAlways, e),
+      Self::FutureSometime(e) => Shape::Unary(Operator::FutureSometime, e),
+      Self::PastAlways(e) => Shape::Unary(Operator::PastAlways, e),
+      Self::PastSometime(e) => Shape::Unary(Operator::PastSometime, e),
+      Self::IndexedNecessary(i, e) => Shape::Unary(Operator::IndexedNecessary(*i), e),
+      Self::IndexedPossible(i, e) => Shape::Unary(Operator::IndexedPossible(*i), e),
+      Self::EverybodyKnows(e) => Shape::Unary(Operator::EverybodyKnows, e),
+      Self::CommonKnowledge(e) => Shape::Unary(Operator::CommonKnowledge, e),
+      Self::And(e1, e2) => Shape::Binary(Operator::And, e1, e2),
+      Self::Or(e1, e2) => Shape::Binary(Operator::Or, e1, e2),
+      Self::Conditional(e1, e2) => Shape::Binary(Operator::Conditional, e1, e2),
+      Self::Biconditional(e1, e2) => Shape::Binary(Operator::Biconditional, e1, e2),
+      Self::Nand(e1, e2) => Shape::Binary(Operator::Nand, e1, e2),
+      Self::Nor(e1, e2) => Shape::Binary(Operator::Nor, e1, e2),
+      Self::Xor(e1, e2) => Shape::Binary(Operator::Xor, e1, e2),
     }
   }
 
-  fn flatten_biconditional(&self) -> Vec<&Self> {
-    match self {
-      Expression::Biconditional(e1, e2) => [e1.flatten_biconditional(), e2.flatten_biconditional()].concat(),
-      e => vec![e],
+  fn from_shape(shape: Shape<Self>) -> Self {
+    match shape {
+      Shape::Variable(v) => Self::Variable(v),
+      Shape::Constant(true) => Self::Top,
+      Shape::Constant(false) => Self::Bottom,
+      Shape::Unary(Operator::Not, e) => Self::Negated(Box::new(e)),
+      Shape::Unary(Operator::Necessary, e) => Self::Necessary(Box::new(e)),
+      Shape::Unary(Operator::Possible, e) => Self::Possible(Box::new(e)),
+      Shape::Unary(Operator::FutureAlways, e) => Self::FutureAlways(Box::new(e)),
+      Shape::Unary(Operator::FutureSometime, e) => Self::FutureSometime(Box::new(e)),
+      Shape::Unary(Operator::PastAlways, e) => Self::PastAlways(Box::new(e)),
+      Shape::Unary(Operator::PastSometime, e) => Self::PastSometime(Box::new(e)),
+      Shape::Unary(Operator::IndexedNecessary(i), e) => Self::IndexedNecessary(i, Box::new(e)),
+      Shape::Unary(Operator::IndexedPossible(i), e) => Self::IndexedPossible(i, Box::new(e)),
+      Shape::Unary(Operator::EverybodyKnows, e) => Self::EverybodyKnows(Box::new(e)),
+      Shape::Unary(Operator::CommonKnowledge, e) => Self::CommonKnowledge(Box::new(e)),
+      Shape::Binary(Operator::And, e1, e2) => Self::And(Box::new(e1), Box::new(e2)),
+      Shape::Binary(Operator::Or, e1, e2) => Self::Or(Box::new(e1), Box::new(e2)),
+      Shape::Binary(Operator::Conditional, e1, e2) => Self::Conditional(Box::new(e1), Box::new(e2)),
+      Shape::Binary(Operator::Biconditional, e1, e2) => Self::Biconditional(Box::new(e1), Box::new(e2)),
+      Shape::Binary(Operator::Nand, e1, e2) => Self::Nand(Box::new(e1), Box::new(e2)),
+      Shape::Binary(Operator::Nor, e1, e2) => Self::Nor(Box::new(e1), Box::new(e2)),
+      Shape::Binary(Operator::Xor, e1, e2) => Self::Xor(Box::new(e1), Box::new(e2)),
+      shape => panic!("{:?} is not a modal operator", shape.operator()),
     }
   }
 }
 
+/// Every classical sentence is also a modal one.
+impl From<classical::Expression> for Expression {
+  fn from(e: classical::Expression) -> Self {
+    e.convert().expect("modal logic has every classical operator")
+  }
+}
+
 impl std::fmt::Display for Expression {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    match self {
-      // TODO: no parens on outermost
-      Self::Variable(v) => write!(f, "{}", v),
-      Self::Negated(e) => write!(f, "¬{}", e),
-      Self::Necessary(e) => write!(f, "◻{}", e),
-      Self::Possible(e) => write!(f, "◇{}", e),
-      e @ Self::And(_, _) => write!(
-        f,
-        "({})",
-        e.flatten_and()
-          .iter()
-          .map(|e| format!("{}", e))
-          .collect::<Vec<_>>()
-          .join(" ∧ ")
-      ),
-      e @ Self::Or(_, _) => write!(
-        f,
-        "({})",
-        e.flatten_or()
-          .iter()
-          .map(|e| format!("{}", e))
-          .collect::<Vec<_>>()
-          .join(" ∨ ")
-      ),
-      e @ Self::Biconditional(_, _) => write!(
-        f,
-        "({})",
-        e.flatten_biconditional()
-          .iter()
-          .map(|e| format!("{}", e))
-          .collect::<Vec<_>>()
-          .join(" ↔ ")
-      ),
-      Self::Conditional(e1, e2) => write!(f, "({} → {})", e1, e2),
-    }
+    printer::write(self, Style::Unicode, f)
   }
 }
 
-#[derive(Debug)]
-pub enum Theorem {
-  Proves { 
-    assumptions: Vec<Expression>,
-    conclusion: Expression,
-  },
-  DoesNotProve {
-    assumptions: Vec<Expression>,
-    conclusion: Expression,
+impl Latex for Expression {
+  fn latex(&self) -> String {
+    printer::print(self, Style::Latex)
   }
 }
 
-impl std::fmt::Display for Theorem {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    let (assumptions, conclusion, op) = match self {
-      Self::Proves{assumptions, conclusion} => (assumptions, conclusion, "⊢"),
-      Self::DoesNotProve{assumptions, conclusion} => (assumptions, conclusion, "⊬"),
-    };
-    let assumptions = assumptions.iter().map(|e| format!("{}", e)).collect::<Vec<_>>().join(", ");
-    write!(f, "{} {} {}", assumptions, op, conclusion)
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_is_valid_theorem() {
+    let holds = |logic: ModalLogic, t: &str| logic.is_valid_theorem(t).unwrap().1;
+    assert!(holds(ModalLogic::K, "[](p -> q) -> ([]p -> []q)"));
+    assert!(holds(ModalLogic::K, "|/- []p -> p"));
+    assert!(!holds(ModalLogic::T, "|/- []p -> p"));
+    assert!(holds(ModalLogic::S4, "[]p |- [][]p"));
+
+    // Thirteen disjunctions give the tableau thousands of branches before it gets to the conclusion.
+    let premises = (1..=13).map(|i| format!("a{} v b{}", i, i)).collect::<Vec<_>>().join(", ");
+    for claim in ["|-", "|/-"].iter() {
+      let e = ModalLogic::K.is_valid_theorem(&format!("{} {} (x ^ y) v ~x v ~y", premises, claim)).unwrap_err();
+      assert_eq!(e.message(), "the tableau grew too large to decide this");
+    }
+
+    for t in ["|- C p", "|- E p", "E p, E q |- C p", "[a]p |/- C p"].iter() {
+      let e = ModalLogic::K.is_valid_theorem(t).unwrap_err();
+      assert_eq!(e.message(), "E and C need at least one agent, named by a K_a or B_a", "{}", t);
+    }
+    assert!(holds(ModalLogic::K, "K_a p |- C p"));
+
+    // Reading off the open branch gives an interpretation whose frame isn't dense.
+    let dense = ModalLogic::new("dense", FrameConditions { dense: true, ..FrameConditions::K });
+    let e = dense.is_valid_theorem("|/- ~(<>p ^ <>q)").unwrap_err();
+    assert_eq!(e.message(), "the tableau's open branch doesn't describe a countermodel");
+    assert!(holds(ModalLogic::KT43, "|/- F p ^ F q -> F (p ^ q)"));
   }
 }
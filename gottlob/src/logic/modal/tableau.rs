@@ -0,0 +1,1137 @@
+//! Prefixed tableaux for the normal modal logic K, following chapter 2 of Priest.
+//!
+//! Every sentence on a branch is labelled with the world it holds at, written `A, i`, and the branch also records
+//! which worlds can see which, written `irj`.  Sentences at the same world contradict each other as usual; the modal
+//! rules move sentences between worlds along the accessibility facts.
+//!
+//! Extensions of K are handled by the extra rules of Priest's chapter 3, which add accessibility facts according to the
+//! `FrameConditions` of the system.
+//!
+//! The same tableaux do for tense logic, reading `irj` as time `i` being earlier than time `j`.  `G` and `F` look
+//! forward along the accessibility facts like `◻` and `◇`, and `H` and `P` look back along them.  Linear time splits a
+//! branch three ways on how two times are ordered, one of the ways being that they are the same time, written `i=j`.
+//!
+//! Indexed operators each look along a relation of their own, written `ir[K_a]j`, with the frame conditions
+//! `FrameConditions::for_index` gives it.  `E` is split into what each agent named in the inference knows, and `C` is
+//! unfolded as `E` of itself: `CA` is `E(A ∧ CA)`.  `¬CA` needs a path to a world where `A` is false, which the branch
+//! can put off one world at a time until the worlds it puts it off to are blocked.  So a finished branch is also closed
+//! when one of its `¬CA` can't reach a world with `¬A` on it along what the agents know, counting a blocked world as
+//! going on wherever the world blocking it does.  Any path to a world where `A` is false can be made to never repeat
+//! what is true at its worlds, so a branch that only loops is no interpretation of the inference, and some other
+//! branch follows the path instead.
+
+use super::countermodel::Countermodel;
+use super::kripke::{KripkeModel, World};
+use super::{Agent, Expression, FrameConditions, Index, Theorem};
+use crate::logic::formula::Operator;
+use crate::logic::latex::{self, Latex};
+use crate::logic::tableau::{Branching, NodeId, Tree, MAX_NODES};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Line {
+  Formula(Expression, World),
+  Accessible(World, World),
+  IndexedAccessible(Index, World, World),
+  Identical(World, World),
+}
+
+impl std::fmt::Display for Line {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Formula(e, i) => write!(f, "{}, {}", e, i),
+      Self::Accessible(i, j) => write!(f, "{}r{}", i, j),
+      Self::IndexedAccessible(x, i, j) => write!(f, "{}r[{}]{}", i, x, j),
+      Self::Identical(i, j) => write!(f, "{}={}", i, j),
+    }
+  }
+}
+
+impl Latex for Line {
+  fn latex(&self) -> String {
+    match self {
+      Self::Formula(e, i) => format!("{}, {}", e.latex(), i),
+      Self::Accessible(i, j) => format!("{}r{}", i, j),
+      Self::IndexedAccessible(x, i, j) => format!("{}r_{{{}}}{}", i, x, j),
+      Self::Identical(i, j) => format!("{}={}", i, j),
+    }
+  }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Rule {
+  Assumption,
+  NegatedConclusion,
+  DoubleNegation,
+  Conjunction,
+  NegatedConjunction,
+  Disjunction,
+  NegatedDisjunction,
+  Conditional,
+  NegatedConditional,
+  Biconditional,
+  NegatedBiconditional,
+  Nand,
+  NegatedNand,
+  Nor,
+  NegatedNor,
+  Xor,
+  NegatedXor,
+  Necessary,
+  NegatedNecessary,
+  Possible,
+  NegatedPossible,
+  FutureAlways,
+  NegatedFutureAlways,
+  FutureSometime,
+  NegatedFutureSometime,
+  PastAlways,
+  NegatedPastAlways,
+  PastSometime,
+  NegatedPastSometime,
+  IndexedNecessary(Index),
+  NegatedIndexedNecessary(Index),
+  IndexedPossible(Index),
+  NegatedIndexedPossible(Index),
+  EverybodyKnows,
+  NegatedEverybodyKnows,
+  CommonKnowledge,
+  NegatedCommonKnowledge,
+  Serial,
+  Reflexive,
+  Symmetric,
+  Transitive,
+  Euclidean,
+  UnboundedPast,
+  Dense,
+  Linear,
+  /// Copying sentences and accessibility facts between worlds that are the same.
+  Identity,
+}
+
+impl Branching for Rule {
+  fn is_branching(&self) -> bool {
+    matches!(
+      self,
+      Self::NegatedConjunction
+        | Self::Disjunction
+        | Self::Conditional
+        | Self::Biconditional
+        | Self::NegatedBiconditional
+        | Self::Nand
+        | Self::NegatedNor
+        | Self::Xor
+        | Self::NegatedXor
+        | Self::NegatedEverybodyKnows
+        | Self::NegatedCommonKnowledge
+        | Self::Linear
+    )
+  }
+}
+
+impl std::fmt::Display for Rule {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let s = match self {
+      Self::Assumption => "A",
+      Self::NegatedConclusion => "¬C",
+      Self::DoubleNegation => "¬¬",
+      Self::Conjunction => "∧",
+      Self::NegatedConjunction => "¬∧",
+      Self::Disjunction => "∨",
+      Self::NegatedDisjunction => "¬∨",
+      Self::Conditional => "→",
+      Self::NegatedConditional => "¬→",
+      Self::Biconditional => "↔",
+      Self::NegatedBiconditional => "¬↔",
+      Self::Nand => "↑",
+      Self::NegatedNand => "¬↑",
+      Self::Nor => "↓",
+      Self::NegatedNor => "¬↓",
+      Self::Xor => "⊕",
+      Self::NegatedXor => "¬⊕",
+      Self::Necessary => "◻",
+      Self::NegatedNecessary => "¬◻",
+      Self::Possible => "◇",
+      Self::NegatedPossible => "¬◇",
+      Self::FutureAlways => "G",
+      Self::NegatedFutureAlways => "¬G",
+      Self::FutureSometime => "F",
+      Self::NegatedFutureSometime => "¬F",
+      Self::PastAlways => "H",
+      Self::NegatedPastAlways => "¬H",
+      Self::PastSometime => "P",
+      Self::NegatedPastSometime => "¬P",
+      Self::IndexedNecessary(x) => return write!(f, "{}", Operator::IndexedNecessary(*x)),
+      Self::NegatedIndexedNecessary(x) => return write!(f, "¬{}", Operator::IndexedNecessary(*x)),
+      Self::IndexedPossible(x) => return write!(f, "{}", Operator::IndexedPossible(*x)),
+      Self::NegatedIndexedPossible(x) => return write!(f, "¬{}", Operator::IndexedPossible(*x)),
+      Self::EverybodyKnows => "E",
+      Self::NegatedEverybodyKnows => "¬E",
+      Self::CommonKnowledge => "C",
+      Self::NegatedCommonKnowledge => "¬C",
+      Self::Serial => "η",
+      Self::Reflexive => "ρ",
+      Self::Symmetric => "σ",
+      Self::Transitive => "τ",
+      Self::Euclidean => "ε",
+      Self::UnboundedPast => "η⁻",
+      Self::Dense => "δ",
+      Self::Linear => "λ",
+      Self::Identity => "=",
+    };
+    write!(f, "{}", s)
+  }
+}
+
+impl Latex for Rule {
+  fn latex(&self) -> String {
+    latex::from_unicode(&self.to_string())
+  }
+}
+
+/// Decomposes sentences whose rule only looks at the world they hold at.  `◻` and `◇`, the tense operators and the
+/// indexed ones are handled separately since they need accessibility facts or a new world.  `E` is about each of
+/// `agents`; with none, everybody knows everything.
+fn decompose(e: &Expression, agents: &BTreeSet<Agent>) -> Option<(Rule, Vec<Vec<Expression>>)> {
+  use Expression::*;
+  let neg = |e: &Expression| Negated(Box::new(e.clone()));
+  let knows = |a: &Agent, e: &Expression| IndexedNecessary(Index::Knowledge(*a), Box::new(e.clone()));
+  // `CA` holds just when `E(A ∧ CA)` does.
+  let unfold = |a: &Expression| {
+    let common = CommonKnowledge(Box::new(a.clone()));
+    EverybodyKnows(Box::new(And(Box::new(a.clone()), Box::new(common))))
+  };
+  let decomposition = match e {
+    Variable(_) | Top | Bottom | Necessary(_) | Possible(_) => return None,
+    FutureAlways(_) | FutureSometime(_) | PastAlways(_) | PastSometime(_) => return None,
+    IndexedNecessary(..) | IndexedPossible(..) => return None,
+    EverybodyKnows(a) => (Rule::EverybodyKnows, vec![agents.iter().map(|x| knows(x, a)).collect()]),
+    CommonKnowledge(a) => (Rule::CommonKnowledge, vec![vec![unfold(a)]]),
+    And(a, b) => (Rule::Conjunction, vec![vec![*a.clone(), *b.clone()]]),
+    Or(a, b) => (Rule::Disjunction, vec![vec![*a.clone()], vec![*b.clone()]]),
+    Conditional(a, b) => (Rule::Conditional, vec![vec![neg(a)], vec![*b.clone()]]),
+    Biconditional(a, b) => (
+      Rule::Biconditional,
+      vec![vec![*a.clone(), *b.clone()], vec![neg(a), neg(b)]],
+    ),
+    Nand(a, b) => (Rule::Nand, vec![vec![neg(a)], vec![neg(b)]]),
+    Nor(a, b) => (Rule::Nor, vec![vec![neg(a), neg(b)]]),
+    Xor(a, b) => (Rule::Xor, vec![vec![*a.clone(), neg(b)], vec![neg(a), *b.clone()]]),
+    Negated(inner) => match inner.as_ref() {
+      Variable(_) | Top | Bottom => return None,
+      Negated(a) => (Rule::DoubleNegation, vec![vec![*a.clone()]]),
+      And(a, b) => (Rule::NegatedConjunction, vec![vec![neg(a)], vec![neg(b)]]),
+      Or(a, b) => (Rule::NegatedDisjunction, vec![vec![neg(a), neg(b)]]),
+      Conditional(a, b) => (Rule::NegatedConditional, vec![vec![*a.clone(), neg(b)]]),
+      Biconditional(a, b) => (
+        Rule::NegatedBiconditional,
+        vec![vec![*a.clone(), neg(b)], vec![neg(a), *b.clone()]],
+      ),
+      Nand(a, b) => (Rule::NegatedNand, vec![vec![*a.clone(), *b.clone()]]),
+      Nor(a, b) => (Rule::NegatedNor, vec![vec![*a.clone()], vec![*b.clone()]]),
+      Xor(a, b) => (Rule::NegatedXor, vec![vec![*a.clone(), *b.clone()], vec![neg(a), neg(b)]]),
+      Necessary(a) => (Rule::NegatedNecessary, vec![vec![Possible(Box::new(neg(a)))]]),
+      Possible(a) => (Rule::NegatedPossible, vec![vec![Necessary(Box::new(neg(a)))]]),
+      FutureAlways(a) => (Rule::NegatedFutureAlways, vec![vec![FutureSometime(Box::new(neg(a)))]]),
+      FutureSometime(a) => (Rule::NegatedFutureSometime, vec![vec![FutureAlways(Box::new(neg(a)))]]),
+      PastAlways(a) => (Rule::NegatedPastAlways, vec![vec![PastSometime(Box::new(neg(a)))]]),
+      PastSometime(a) => (Rule::NegatedPastSometime, vec![vec![PastAlways(Box::new(neg(a)))]]),
+      IndexedNecessary(x, a) => (Rule::NegatedIndexedNecessary(*x), vec![vec![IndexedPossible(*x, Box::new(neg(a)))]]),
+      IndexedPossible(x, a) => (Rule::NegatedIndexedPossible(*x), vec![vec![IndexedNecessary(*x, Box::new(neg(a)))]]),
+      EverybodyKnows(_) if agents.is_empty() => (Rule::NegatedEverybodyKnows, vec![vec![Bottom]]),
+      EverybodyKnows(a) => (Rule::NegatedEverybodyKnows, agents.iter().map(|x| vec![neg(&knows(x, a))]).collect()),
+      CommonKnowledge(_) => return None,
+    },
+  };
+  Some(decomposition)
+}
+
+/// One step of the tableau: add each of `branches` below a leaf.
+struct Application {
+  source: Option<NodeId>,
+  rule: Rule,
+  branches: Vec<Vec<Line>>,
+  /// Whether this uses up `Tableau::next_world`.
+  creates_world: bool,
+}
+
+impl Application {
+  fn new(source: Option<NodeId>, rule: Rule, line: Line) -> Self {
+    Self {
+      source,
+      rule,
+      branches: vec![vec![line]],
+      creates_world: false,
+    }
+  }
+}
+
+/// Which way a modal rule looks from a world: along the accessibility facts, back along them, or along the relation
+/// of an index.
+#[derive(Clone, Copy)]
+enum Along {
+  Forwards,
+  Backwards,
+  Indexed(Index),
+}
+
+/// A sentence's rule, and the lines each of the branches it makes starts with.
+type Decomposition = (Rule, Vec<Vec<Line>>);
+
+/// The worlds `i` sees in `access`.
+fn seen(access: &BTreeSet<(World, World)>, i: World) -> impl Iterator<Item = World> + '_ {
+  access.range((i, World::MIN)..=(i, World::MAX)).map(|(_, j)| *j)
+}
+
+/// The pairs of worlds some world sees both of in `access`.
+fn seen_together(access: &BTreeSet<(World, World)>) -> impl Iterator<Item = (World, World)> + '_ {
+  access.iter().flat_map(move |(i, j)| seen(access, *i).map(move |k| (*j, k)))
+}
+
+/// What is on a branch, indexed for the rules' side conditions, and kept up to date as the branch grows.
+#[derive(Clone)]
+struct Branch {
+  leaf: NodeId,
+  lines: Vec<(NodeId, Line)>,
+  /// The sentences true at each world.
+  formulas: BTreeMap<World, HashSet<Expression>>,
+  access: BTreeSet<(World, World)>,
+  /// The accessibility facts turned around, for looking back along them.
+  reversed: BTreeSet<(World, World)>,
+  indexed: BTreeMap<Index, BTreeSet<(World, World)>>,
+  identical: Vec<(NodeId, World, World)>,
+  worlds: BTreeSet<World>,
+  /// The nodes some rule has been applied to.
+  sources: HashSet<NodeId>,
+  /// The sentences still to be decomposed in place, by whether their rule branches.
+  pending: [BTreeMap<NodeId, Decomposition>; 2],
+}
+
+impl Branch {
+  fn new(tree: &Tree<Line, Rule>, leaf: NodeId, agents: &BTreeSet<Agent>) -> Self {
+    let mut branch = Self {
+      leaf,
+      lines: Vec::new(),
+      formulas: BTreeMap::new(),
+      access: BTreeSet::new(),
+      reversed: BTreeSet::new(),
+      indexed: BTreeMap::new(),
+      identical: Vec::new(),
+      worlds: BTreeSet::new(),
+      sources: HashSet::new(),
+      pending: [BTreeMap::new(), BTreeMap::new()],
+    };
+    for id in tree.branch(leaf) {
+      branch.push(tree, id, agents);
+    }
+    branch
+  }
+
+  /// Extends the branch with the node `id` of `tree`, which must be a child of its leaf.
+  fn push(&mut self, tree: &Tree<Line, Rule>, id: NodeId, agents: &BTreeSet<Agent>) {
+    let node = tree.node(id);
+    self.leaf = id;
+    self.sources.extend(node.source);
+    match &node.content {
+      Line::Formula(e, i) => {
+        if let Some((rule, branches)) = decompose(e, agents) {
+          let branches = branches.into_iter().map(|b| b.into_iter().map(|e| Line::Formula(e, *i)).collect()).collect();
+          self.pending[rule.is_branching() as usize].insert(id, (rule, branches));
+        }
+        self.formulas.entry(*i).or_default().insert(e.clone());
+        self.worlds.insert(*i);
+      }
+      Line::Accessible(i, j) => {
+        self.access.insert((*i, *j));
+        self.reversed.insert((*j, *i));
+        self.worlds.insert(*i);
+        self.worlds.insert(*j);
+      }
+      Line::IndexedAccessible(x, i, j) => {
+        self.indexed.entry(*x).or_default().insert((*i, *j));
+        self.worlds.insert(*i);
+        self.worlds.insert(*j);
+      }
+      Line::Identical(i, j) => self.identical.push((id, *i, *j)),
+    }
+    self.lines.push((id, node.content.clone()));
+  }
+
+  fn has(&self, e: &Expression, i: World) -> bool {
+    self.formulas.get(&i).is_some_and(|formulas| formulas.contains(e))
+  }
+
+  /// Every sentence on the branch, with the world it is true at.
+  fn formulas(&self) -> impl Iterator<Item = (&Expression, World)> {
+    self.formulas.iter().flat_map(|(i, formulas)| formulas.iter().map(move |e| (e, *i)))
+  }
+
+  fn indexed_access(&self, index: Index) -> &BTreeSet<(World, World)> {
+    const NONE: &BTreeSet<(World, World)> = &BTreeSet::new();
+    self.indexed.get(&index).unwrap_or(NONE)
+  }
+
+  /// The worlds `i` sees, or that see it if `along` is backwards.
+  fn reached(&self, i: World, along: Along) -> Vec<World> {
+    let access = match along {
+      Along::Forwards => &self.access,
+      Along::Backwards => &self.reversed,
+      Along::Indexed(x) => self.indexed_access(x),
+    };
+    seen(access, i).collect()
+  }
+
+  fn contains(&self, line: &Line) -> bool {
+    match line {
+      Line::Formula(e, i) => self.has(e, *i),
+      Line::Accessible(i, j) => self.access.contains(&(*i, *j)),
+      Line::IndexedAccessible(x, i, j) => self.indexed_access(*x).contains(&(*i, *j)),
+      Line::Identical(i, j) => self.identical.iter().any(|(_, k, l)| (k, l) == (i, j) || (k, l) == (j, i)),
+    }
+  }
+
+  /// Whether adding `line` to the branch would close it, a sentence being false at a world it is true at.
+  fn closes(&self, line: &Line) -> bool {
+    match line {
+      Line::Formula(Expression::Bottom, _) => true,
+      Line::Formula(Expression::Negated(inner), i) => **inner == Expression::Top || self.has(inner, *i),
+      Line::Formula(e, i) => self.has(&Expression::Negated(Box::new(e.clone())), *i),
+      _ => false,
+    }
+  }
+
+  /// Whether there is anything on the branch about how `i` and `j` are ordered.
+  fn orders(&self, i: World, j: World) -> bool {
+    [Line::Accessible(i, j), Line::Accessible(j, i), Line::Identical(i, j)].iter().any(|line| self.contains(line))
+  }
+
+  /// Whether some world reached from `i` in one or more steps along what any of `agents` knows has `¬A` on it.  A
+  /// blocked world also goes on to wherever the world blocking it does, which is how a branch loops back on itself.
+  fn fulfils(&self, a: &Expression, i: World, agents: &BTreeSet<Agent>) -> bool {
+    let negated = Expression::Negated(Box::new(a.clone()));
+    let mut reached = BTreeSet::new();
+    let mut todo = vec![i];
+    while let Some(w) = todo.pop() {
+      for from in Some(w).into_iter().chain(self.blocker(w)) {
+        for x in agents {
+          for v in self.reached(from, Along::Indexed(Index::Knowledge(*x))) {
+            if reached.insert(v) {
+              todo.push(v);
+            }
+          }
+        }
+      }
+    }
+    reached.into_iter().any(|w| self.has(&negated, w))
+  }
+
+  /// Whether the branch has a `¬CA` that was handed on from world to world until it looped back through a blocked
+  /// world, without ever getting to a world where `A` is false.
+  fn is_unfulfilled(&self, agents: &BTreeSet<Agent>) -> bool {
+    self.formulas().any(|(e, i)| match e {
+      Expression::Negated(inner) => match inner.as_ref() {
+        Expression::CommonKnowledge(a) => !self.fulfils(a, i, agents),
+        _ => false,
+      },
+      _ => false,
+    })
+  }
+
+  /// A world is blocked when an earlier world has exactly the same sentences true at it: whatever worlds it would need
+  /// can be borrowed from the earlier one, so we don't create any.  This is what keeps tableaux for transitive and
+  /// serial frames finite.
+  fn blocker(&self, world: World) -> Option<World> {
+    let formulas = self.formulas.get(&world);
+    self.worlds.range(..world).find(|w| self.formulas.get(*w) == formulas).cloned()
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Tableau {
+  tree: Tree<Line, Rule>,
+  frame_conditions: FrameConditions,
+  /// Everyone with a `K_a` or `B_a` in the inference, who `E` and `C` are about.
+  agents: BTreeSet<Agent>,
+  /// The indexed relations the rules look after: those of the indexed operators in the inference, and what each agent
+  /// knows.
+  indices: BTreeSet<Index>,
+  next_world: World,
+  complete: bool,
+}
+
+impl Tableau {
+  /// Builds and completes the tableau for the inference from `assumptions` to `conclusion`, all evaluated at world 0,
+  /// over frames satisfying `frame_conditions`.
+  pub fn new(assumptions: &[Expression], conclusion: &Expression, frame_conditions: FrameConditions) -> Self {
+    let mut indices = assumptions.iter().chain(Some(conclusion)).flat_map(|e| e.indices()).collect::<BTreeSet<_>>();
+    let agents = assumptions.iter().chain(Some(conclusion)).flat_map(|e| e.agents()).collect::<BTreeSet<_>>();
+    indices.extend(agents.iter().map(|a| Index::Knowledge(*a)));
+    let mut tableau = Self {
+      tree: Tree::new(),
+      frame_conditions,
+      agents,
+      indices,
+      next_world: 1,
+      complete: false,
+    };
+    let mut leaf = None;
+    for a in assumptions {
+      leaf = Some(tableau.tree.push(leaf, Line::Formula(a.clone(), 0), Rule::Assumption, None));
+    }
+    let negated_conclusion = Line::Formula(Expression::Negated(Box::new(conclusion.clone())), 0);
+    let leaf = tableau.tree.push(leaf, negated_conclusion, Rule::NegatedConclusion, None);
+    tableau.complete(leaf);
+    tableau
+  }
+
+  pub fn for_theorem(thm: &Theorem, frame_conditions: FrameConditions) -> Self {
+    Self::new(thm.assumptions(), thm.conclusion(), frame_conditions)
+  }
+
+  pub fn tree(&self) -> &Tree<Line, Rule> {
+    &self.tree
+  }
+
+  pub fn frame_conditions(&self) -> FrameConditions {
+    self.frame_conditions
+  }
+
+  /// A closed tableau means the inference is valid.
+  pub fn is_closed(&self) -> bool {
+    self.tree.is_closed()
+  }
+
+  /// False if we gave up because the tableau grew too large, in which case an open branch proves nothing.
+  pub fn is_complete(&self) -> bool {
+    self.complete
+  }
+
+  pub fn open_branches(&self) -> Vec<Vec<&Line>> {
+    self.tree.open_leaves().into_iter().map(|leaf| self.tree.branch_contents(leaf)).collect()
+  }
+
+  /// The interpretation described by the first open branch, if there is one.  A blocked world sees whatever the world
+  /// blocking it sees, and is seen by whatever sees that world.  Worlds that are the same become one world, named
+  /// after the earliest of them.
+  pub fn countermodel(&self) -> Option<Countermodel> {
+    if !self.complete {
+      return None;
+    }
+    let leaf = *self.tree.open_leaves().first()?;
+    let (model, _) = self.read_off(&Branch::new(&self.tree, leaf, &self.agents));
+    Some(Countermodel { model })
+  }
+
+  /// The interpretation an open branch describes, and which world of it each world on the branch became.
+  fn read_off(&self, branch: &Branch) -> (KripkeModel, BTreeMap<World, World>) {
+    let mut same = branch.worlds.iter().map(|w| (*w, *w)).collect::<BTreeMap<_, _>>();
+    loop {
+      let mut changed = false;
+      for (_, i, j) in &branch.identical {
+        let first = same[i].min(same[j]);
+        for w in [i, j].iter() {
+          changed |= same.insert(**w, first) != Some(first);
+        }
+      }
+      if !changed {
+        break;
+      }
+    }
+    let mut model = KripkeModel::new();
+    model.agents = self.agents.clone();
+    for w in &branch.worlds {
+      model.add_world(same[w]);
+    }
+    for (w, v) in &branch.access {
+      model.add_access(same[w], same[v]);
+    }
+    for (x, access) in &branch.indexed {
+      for (w, v) in access {
+        model.add_indexed_access(*x, same[w], same[v]);
+      }
+    }
+    for (e, w) in branch.formulas() {
+      if let Expression::Variable(v) = e {
+        model.set_true(same[&w], *v);
+      }
+    }
+    for w in &branch.worlds {
+      if let Some(blocker) = branch.blocker(*w) {
+        let w = same[w];
+        for (from, to) in &branch.access {
+          if *from == blocker {
+            model.add_access(w, same[to]);
+          }
+          if *to == blocker {
+            model.add_access(same[from], w);
+          }
+        }
+        for (x, access) in &branch.indexed {
+          for (_, to) in access.iter().filter(|(from, _)| *from == blocker) {
+            model.add_indexed_access(*x, w, same[to]);
+          }
+        }
+      }
+    }
+    model.close_under(self.frame_conditions);
+    for x in &self.indices {
+      model.close_indexed_under(*x, self.frame_conditions.for_index(*x));
+    }
+    (model, same)
+  }
+
+  /// Applies rules to the branch ending at `leaf` until every branch below it is closed or finished.  Each open branch
+  /// keeps track of what is on it as it grows, and the rules go to the one with the earliest leaf first.
+  fn complete(&mut self, leaf: NodeId) {
+    let branch = Branch::new(&self.tree, leaf, &self.agents);
+    let mut open = BTreeMap::new();
+    if branch.lines.iter().any(|(_, line)| branch.closes(line)) {
+      self.tree.close(leaf);
+    } else {
+      open.insert(leaf, branch);
+    }
+    while let Some((_, mut branch)) = open.pop_first() {
+      if self.tree.len() > MAX_NODES {
+        return;
+      }
+      let application = match self.next_application(&mut branch) {
+        Some(application) => application,
+        None => {
+          // Once nothing else applies, a branch is closed if a `¬CA` on it only loops.
+          if branch.is_unfulfilled(&self.agents) {
+            self.tree.close(branch.leaf);
+          }
+          continue;
+        }
+      };
+      if application.creates_world {
+        self.next_world += 1;
+      }
+      let branches = vec![branch; application.branches.len()];
+      for (lines, mut branch) in application.branches.iter().zip(branches) {
+        let mut closes = false;
+        for line in lines {
+          let id = self.tree.push(Some(branch.leaf), line.clone(), application.rule, application.source);
+          branch.push(&self.tree, id, &self.agents);
+          closes |= branch.closes(line);
+        }
+        if closes {
+          self.tree.close(branch.leaf);
+        } else {
+          open.insert(branch.leaf, branch);
+        }
+      }
+    }
+    self.complete = true;
+  }
+
+  /// Picks the next rule to apply to a branch, or `None` if the branch is finished.  Rules that don't split the branch
+  /// or make new worlds go first, which keeps trees small.
+  fn next_application(&self, branch: &mut Branch) -> Option<Application> {
+    Self::next_local(branch, false)
+      .or_else(|| Self::next_necessary(branch))
+      .or_else(|| Self::next_identity(branch))
+      .or_else(|| self.next_frame_condition(branch))
+      .or_else(|| self.next_possible(branch))
+      .or_else(|| self.next_common_knowledge(branch))
+      .or_else(|| self.next_serial(branch))
+      .or_else(|| self.next_unbounded_past(branch))
+      .or_else(|| self.next_dense(branch))
+      .or_else(|| Self::next_local(branch, true))
+      .or_else(|| self.next_linear(branch))
+  }
+
+  /// Takes the next sentence waiting to be decomposed in place, skipping those whose decomposition is already there.
+  fn next_local(branch: &mut Branch, branching: bool) -> Option<Application> {
+    loop {
+      let (id, (rule, branches)) = branch.pending[branching as usize].pop_first()?;
+      if !branches.iter().any(|b| b.iter().all(|line| branch.contains(line))) {
+        return Some(Application {
+          source: Some(id),
+          rule,
+          branches,
+          creates_world: false,
+        });
+      }
+    }
+  }
+
+  /// Finds a `◻A, i` or `GA, i` and an `irj`, an `HA, i` and a `jri`, or a `K_aA, i` and an `ir[K_a]j`, such that
+  /// `A, j` isn't on the branch yet.
+  fn next_necessary(branch: &Branch) -> Option<Application> {
+    branch.lines.iter().find_map(|(id, line)| {
+      let (rule, a, i, along) = match line {
+        Line::Formula(Expression::Necessary(a), i) => (Rule::Necessary, a, *i, Along::Forwards),
+        Line::Formula(Expression::FutureAlways(a), i) => (Rule::FutureAlways, a, *i, Along::Forwards),
+        Line::Formula(Expression::PastAlways(a), i) => (Rule::PastAlways, a, *i, Along::Backwards),
+        Line::Formula(Expression::IndexedNecessary(x, a), i) => (Rule::IndexedNecessary(*x), a, *i, Along::Indexed(*x)),
+        _ => return None,
+      };
+      branch.reached(i, along).into_iter().find_map(|j| {
+        let line = Line::Formula(*a.clone(), j);
+        if branch.contains(&line) {
+          None
+        } else {
+          Some(Application::new(Some(*id), rule, line))
+        }
+      })
+    })
+  }
+
+  /// Given `i=j`, copies a sentence or accessibility fact about either world over to the other.
+  fn next_identity(branch: &Branch) -> Option<Application> {
+    branch.identical.iter().find_map(|(id, i, j)| {
+      let other = |w: World| if w == *i { *j } else if w == *j { *i } else { w };
+      let formulas = branch.formulas().map(|(e, w)| Line::Formula(e.clone(), other(w)));
+      let access = branch.access.iter().map(|(v, w)| Line::Accessible(other(*v), other(*w)));
+      let indexed = branch.indexed.iter().flat_map(|(x, access)| {
+        access.iter().map(move |(v, w)| Line::IndexedAccessible(*x, other(*v), other(*w)))
+      });
+      let line = formulas.chain(access).chain(indexed).find(|line| !branch.contains(line))?;
+      Some(Application::new(Some(*id), Rule::Identity, line))
+    })
+  }
+
+  /// Adds an accessibility fact required by the frame conditions, on the relation of `◻` or of an index.
+  fn next_frame_condition(&self, branch: &Branch) -> Option<Application> {
+    Self::next_frame_condition_on(branch, &branch.access, self.frame_conditions, Line::Accessible).or_else(|| {
+      self.indices.iter().find_map(|x| {
+        let line = |i, j| Line::IndexedAccessible(*x, i, j);
+        Self::next_frame_condition_on(branch, branch.indexed_access(*x), self.frame_conditions.for_index(*x), line)
+      })
+    })
+  }
+
+  /// Adds an accessibility fact that `access` is missing under `conditions`, written with `line`.
+  fn next_frame_condition_on(
+    branch: &Branch,
+    access: &BTreeSet<(World, World)>,
+    conditions: FrameConditions,
+    line: impl Fn(World, World) -> Line,
+  ) -> Option<Application> {
+    let missing = |rule, line: Line| {
+      if branch.contains(&line) {
+        None
+      } else {
+        Some(Application::new(None, rule, line))
+      }
+    };
+    if conditions.reflexive {
+      let found = branch.worlds.iter().find_map(|i| missing(Rule::Reflexive, line(*i, *i)));
+      if found.is_some() {
+        return found;
+      }
+    }
+    for (i, j) in access {
+      if conditions.symmetric {
+        if let Some(found) = missing(Rule::Symmetric, line(*j, *i)) {
+          return Some(found);
+        }
+      }
+      if conditions.transitive {
+        if let Some(found) = seen(access, *j).find_map(|l| missing(Rule::Transitive, line(*i, l))) {
+          return Some(found);
+        }
+      }
+      if conditions.euclidean {
+        if let Some(found) = seen(access, *i).find_map(|l| missing(Rule::Euclidean, line(*j, l))) {
+          return Some(found);
+        }
+      }
+    }
+    None
+  }
+
+  /// Finds a `◇A, i`, `FA, i` or `⟨K_a⟩A, i` with no witness yet and sends `A` to a brand new world that `i` sees, or
+  /// a `PA, i` and sends `A` to a brand new world that sees `i`.
+  fn next_possible(&self, branch: &Branch) -> Option<Application> {
+    let (id, rule, a, i, along) = branch.lines.iter().find_map(|(id, line)| {
+      let (rule, a, i, along) = match line {
+        Line::Formula(Expression::Possible(a), i) => (Rule::Possible, a, *i, Along::Forwards),
+        Line::Formula(Expression::FutureSometime(a), i) => (Rule::FutureSometime, a, *i, Along::Forwards),
+        Line::Formula(Expression::PastSometime(a), i) => (Rule::PastSometime, a, *i, Along::Backwards),
+        Line::Formula(Expression::IndexedPossible(x, a), i) => (Rule::IndexedPossible(*x), a, *i, Along::Indexed(*x)),
+        _ => return None,
+      };
+      let witnessed = branch.reached(i, along).into_iter().any(|j| branch.has(a, j));
+      if witnessed || branch.blocker(i).is_some() {
+        None
+      } else {
+        Some((*id, rule, a, i, along))
+      }
+    })?;
+    let j = self.next_world;
+    let access = match along {
+      Along::Forwards => Line::Accessible(i, j),
+      Along::Backwards => Line::Accessible(j, i),
+      Along::Indexed(x) => Line::IndexedAccessible(x, i, j),
+    };
+    Some(Application {
+      source: Some(id),
+      rule,
+      branches: vec![vec![access, Line::Formula(*a.clone(), j)]],
+      creates_world: true,
+    })
+  }
+
+  /// Finds a `¬CA, i` not dealt with yet, and splits the branch on which agent can't tell `i` from a brand new world
+  /// and whether `A` is false there or only not common knowledge.  Only a world where `A` is already false counts as a
+  /// witness, since `¬CA` itself could be handed on from world to world forever.
+  fn next_common_knowledge(&self, branch: &Branch) -> Option<Application> {
+    let (id, a, i) = branch.lines.iter().find_map(|(id, line)| {
+      let (a, i) = match line {
+        Line::Formula(Expression::Negated(inner), i) => match inner.as_ref() {
+          Expression::CommonKnowledge(a) => (a, *i),
+          _ => return None,
+        },
+        _ => return None,
+      };
+      let negated = Expression::Negated(a.clone());
+      let mut reached = self.agents.iter().flat_map(|x| branch.reached(i, Along::Indexed(Index::Knowledge(*x))));
+      let witnessed = reached.any(|j| branch.has(&negated, j));
+      if witnessed || branch.sources.contains(id) || branch.blocker(i).is_some() {
+        None
+      } else {
+        Some((*id, a, i))
+      }
+    })?;
+    let j = self.next_world;
+    let negated = Expression::Negated(a.clone());
+    let postponed = Expression::Negated(Box::new(Expression::CommonKnowledge(a.clone())));
+    let mut branches = Vec::new();
+    for x in &self.agents {
+      for e in [&negated, &postponed].iter() {
+        branches.push(vec![Line::IndexedAccessible(Index::Knowledge(*x), i, j), Line::Formula((*e).clone(), j)]);
+      }
+    }
+    if branches.is_empty() {
+      // With nobody to know anything, everything is common knowledge.
+      branches.push(vec![Line::Formula(Expression::Bottom, i)]);
+    }
+    Some(Application {
+      source: Some(id),
+      rule: Rule::NegatedCommonKnowledge,
+      branches,
+      creates_world: !self.agents.is_empty(),
+    })
+  }
+
+  /// On serial frames, gives any world that can't see anything a new world to see.  Serial indexed relations, such as
+  /// belief, get the same.
+  fn next_serial(&self, branch: &Branch) -> Option<Application> {
+    let j = self.next_world;
+    let stuck = |access: &BTreeSet<(World, World)>| {
+      let mut worlds = branch.worlds.iter();
+      worlds.find(|i| seen(access, **i).next().is_none() && branch.blocker(**i).is_none()).cloned()
+    };
+    let plain = Some(&branch.access).filter(|_| self.frame_conditions.serial);
+    let line = plain.and_then(stuck).map(|i| Line::Accessible(i, j)).or_else(|| {
+      let mut serial = self.indices.iter().filter(|x| self.frame_conditions.for_index(**x).serial);
+      serial.find_map(|x| stuck(branch.indexed_access(*x)).map(|i| Line::IndexedAccessible(*x, i, j)))
+    })?;
+    Some(Application {
+      creates_world: true,
+      ..Application::new(None, Rule::Serial, line)
+    })
+  }
+
+  /// When the past is unbounded, gives any world that nothing sees a new world to see it.
+  fn next_unbounded_past(&self, branch: &Branch) -> Option<Application> {
+    if !self.frame_conditions.unbounded_past {
+      return None;
+    }
+    let i = *branch
+      .worlds
+      .iter()
+      .find(|i| seen(&branch.reversed, **i).next().is_none() && branch.blocker(**i).is_none())?;
+    Some(Application {
+      creates_world: true,
+      ..Application::new(None, Rule::UnboundedPast, Line::Accessible(self.next_world, i))
+    })
+  }
+
+  /// On dense frames, puts a new world between any `irj` with nothing between them yet.
+  fn next_dense(&self, branch: &Branch) -> Option<Application> {
+    if !self.frame_conditions.dense {
+      return None;
+    }
+    let (i, j) = *branch.access.iter().find(|(i, j)| {
+      let between = seen(&branch.access, *i).any(|k| branch.access.contains(&(k, *j)));
+      !between && branch.blocker(*i).is_none() && branch.blocker(*j).is_none()
+    })?;
+    let k = self.next_world;
+    Some(Application {
+      source: None,
+      rule: Rule::Dense,
+      branches: vec![vec![Line::Accessible(i, k), Line::Accessible(k, j)]],
+      creates_world: true,
+    })
+  }
+
+  /// On linear frames, two worlds seen by the same world, or that both see the same world, must come one before the
+  /// other or be the same, so the branch splits three ways on which it is.
+  fn next_linear(&self, branch: &Branch) -> Option<Application> {
+    if !self.frame_conditions.linear {
+      return None;
+    }
+    // Pairs of worlds seen by the same world, then pairs that see the same world.
+    let (j, k) = seen_together(&branch.access)
+      .chain(seen_together(&branch.reversed))
+      .find(|(j, k)| j != k && !branch.orders(*j, *k))?;
+    Some(Application {
+      source: None,
+      rule: Rule::Linear,
+      branches: vec![
+        vec![Line::Accessible(j, k)],
+        vec![Line::Identical(j, k)],
+        vec![Line::Accessible(k, j)],
+      ],
+      creates_world: false,
+    })
+  }
+}
+
+impl std::fmt::Display for Tableau {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.tree)
+  }
+}
+
+impl Latex for Tableau {
+  fn latex(&self) -> String {
+    self.tree.latex()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::modal::parser::ModalParser;
+
+  fn is_valid(thm: &str, frame_conditions: FrameConditions) -> bool {
+    let tableau = Tableau::for_theorem(&ModalParser::parse_theorem(thm).unwrap(), frame_conditions);
+    assert!(tableau.is_complete());
+    tableau.is_closed()
+  }
+
+  #[test]
+  fn test_valid_in_k() {
+    let k = FrameConditions::K;
+    assert!(is_valid("|- p v ~p", k));
+    assert!(is_valid("|- [](p -> q) -> ([]p -> []q)", k));
+    assert!(is_valid("[]p, []q |- [](p ^ q)", k));
+    assert!(is_valid("|- <>p <-> ~[]~p", k));
+    assert!(is_valid("|- [](p ^ q) -> []p ^ []q", k));
+    assert!(is_valid("|- <>(p v q) -> <>p v <>q", k));
+    assert!(is_valid("[]p, <>q |- <>(p ^ q)", k));
+    assert!(is_valid("|- []T", k));
+    assert!(is_valid("<>p |- ~[]F", k));
+    assert!(is_valid("|- ([]p nand []q) <-> ~([]p ^ []q)", k));
+    assert!(is_valid("[](p nor q) |- []~p", k));
+    assert!(is_valid("<>(p xor q) |- <>p v <>q", k));
+  }
+
+  #[test]
+  fn test_invalid_in_k() {
+    let k = FrameConditions::K;
+    assert!(!is_valid("|- []p -> p", k));
+    assert!(!is_valid("|- p -> []<>p", k));
+    assert!(!is_valid("|- []p -> [][]p", k));
+    assert!(!is_valid("|- []p -> <>p", k));
+    assert!(!is_valid("<>p, <>q |- <>(p ^ q)", k));
+    assert!(!is_valid("|- <>T", k));
+    assert!(!is_valid("<>p, <>q |- ~[](p xor q)", k));
+  }
+
+  #[test]
+  fn test_characteristic_axioms() {
+    use FrameConditions as F;
+    let axioms = [
+      ("|- []p -> <>p", F::D),
+      ("|- []p -> p", F::T),
+      ("|- p -> []<>p", F::B),
+      ("|- []p -> [][]p", F::S4),
+      ("|- <>p -> []<>p", F::S5),
+    ];
+    for (axiom, system) in axioms.iter() {
+      assert!(is_valid(axiom, *system), "{} in {:?}", axiom, system);
+      assert!(!is_valid(axiom, F::K), "{} in K", axiom);
+    }
+    assert!(!is_valid("|- []p -> p", F::D));
+    assert!(!is_valid("|- []p -> [][]p", F::B));
+    assert!(!is_valid("|- p -> []<>p", F::S4));
+    assert!(is_valid("|- p -> []<>p", F::S5));
+    assert!(is_valid("|- []p -> [][]p", F::S5));
+    assert!(is_valid("|- []p -> p", F::K.union(F::T)));
+    assert!(is_valid("|- <>T", F::D));
+  }
+
+  #[test]
+  fn test_infinite_models_terminate() {
+    use FrameConditions as F;
+    assert!(!is_valid("|- ~[]<>p", F::S4));
+    assert!(!is_valid("[]<>p |- <>[]p", F::S4));
+    assert!(!is_valid("|- ~<>p", F::D));
+    assert!(!is_valid("[]<>p, []<>~p |- p", F::D.union(F::S4)));
+  }
+
+  #[test]
+  fn test_countermodels() {
+    use FrameConditions as F;
+    let cases = [
+      ("|- []p -> p", F::K),
+      ("|- p -> []<>p", F::S4),
+      ("<>p, <>q |- <>(p ^ q)", F::K),
+      ("|- []p -> [][]p", F::B),
+      ("|- ~[]<>p", F::S4),
+      ("[]<>p |- <>[]p", F::S4),
+      ("[]<>p, []<>~p |- p", F::D.union(F::S4)),
+      ("<>p, <>~p |- []q", F::S5),
+      ("|- []p v []~p", F::S5),
+      ("|- <>p -> []p", F::D),
+      ("|- <>T v []F -> p", F::K),
+    ];
+    for (thm, system) in cases.iter() {
+      let thm = ModalParser::parse_theorem(thm).unwrap();
+      let t = Tableau::for_theorem(&thm, *system);
+      let countermodel = t.countermodel().expect("invalid inferences have countermodels");
+      assert!(
+        countermodel.is_countermodel(thm.assumptions(), thm.conclusion()),
+        "{} in {:?}:\n{}",
+        thm,
+        system,
+        countermodel
+      );
+      assert!(countermodel.model.satisfies(*system), "{} in {:?} respects the frame conditions", thm, system);
+    }
+  }
+
+  #[test]
+  fn test_tense() {
+    use FrameConditions as F;
+    assert!(is_valid("|- p -> GPp", F::KT));
+    assert!(is_valid("|- p -> HFp", F::KT));
+    assert!(is_valid("|- PGp -> p", F::KT));
+    assert!(is_valid("|- G(p -> q) -> (Gp -> Gq)", F::KT));
+    assert!(is_valid("|- ~G~p <-> Fp", F::KT));
+    assert!(!is_valid("|- Gp -> GGp", F::KT));
+    assert!(is_valid("|- Gp -> GGp", F::KT4));
+    assert!(is_valid("|- Hp -> HHp", F::KT4));
+    assert!(!is_valid("|- Fp -> FFp", F::KT4));
+    assert!(is_valid("|- Fp -> FFp", F::Q));
+    assert!(is_valid("|- Gp -> Fp", F::Q));
+    assert!(is_valid("|- Hp -> Pp", F::Q));
+    assert!(!is_valid("|- Hp -> Pp", F::KT43));
+    let connected = "|- Fp ^ Fq -> F(p ^ q) v F(p ^ Fq) v F(q ^ Fp)";
+    assert!(!is_valid(connected, F::KT4));
+    assert!(is_valid(connected, F::KT43));
+    assert!(is_valid("|- Pp ^ Pq -> P(p ^ q) v P(p ^ Pq) v P(q ^ Pp)", F::KT43));
+  }
+
+  #[test]
+  fn test_tense_countermodels() {
+    use FrameConditions as F;
+    let cases = [
+      ("|- Gp -> GGp", F::KT),
+      ("|- p -> GFp", F::KT),
+      ("|- Fp -> FFp", F::KT4),
+      ("|- Hp -> Pp", F::KT43),
+      ("|- Fp ^ Fq -> F(p ^ q) v F(p ^ Fq) v F(q ^ Fp)", F::KT4),
+      ("|- Fp -> Pp", F::KT43),
+    ];
+    for (thm, system) in cases.iter() {
+      let thm = ModalParser::parse_theorem(thm).unwrap();
+      let t = Tableau::for_theorem(&thm, *system);
+      let countermodel = t.countermodel().expect("invalid inferences have countermodels");
+      assert!(countermodel.is_countermodel(thm.assumptions(), thm.conclusion()), "{}:\n{}", thm, countermodel);
+      assert!(countermodel.model.satisfies(*system), "{} in {:?} respects the frame conditions", thm, system);
+    }
+  }
+
+  #[test]
+  fn test_epistemic() {
+    use FrameConditions as F;
+    let valid = [
+      "K_a p |- p",
+      "K_a p |- K_a K_a p",
+      "~K_a p |- K_a ~K_a p",
+      "B_a p |- B_a B_a p",
+      "~B_a p |- B_a ~B_a p",
+      "|- ~B_a F",
+      "K_a p, K_a (p -> q) |- K_a q",
+      "E p, B_b q |- K_a p ^ K_b p",
+      "C p, B_b q |- K_a K_b K_a p",
+      "C p, K_b q |- E C p",
+      "[a]p, [a](p -> q) |- [a]q",
+      "|- <K_a>p <-> ~K_a~p",
+    ];
+    for thm in valid.iter() {
+      assert!(is_valid(thm, F::K), "{}", thm);
+    }
+    let invalid = [
+      "B_a p |- p",
+      "K_a p |- K_b p",
+      "B_a p |- K_a p",
+      "[a]p |- p",
+      "[a]p |- [b]p",
+      "K_a p, K_b p |- E E p",
+      "K_a p, K_b p |- C p",
+      "K_a p, K_b p, E E p |- C p",
+      "C p, K_a q, K_b q |- C q",
+    ];
+    for thm in invalid.iter() {
+      assert!(!is_valid(thm, F::K), "{}", thm);
+    }
+    assert!(is_valid("K_a p |- C p", F::K), "what one agent knows is common knowledge to that agent alone");
+    assert!(is_valid("[a]p |- p", F::T));
+    assert!(!is_valid("[a]p |- [a][a]p", F::T));
+  }
+
+  #[test]
+  fn test_epistemic_countermodels() {
+    let cases = [
+      "B_a p |- p",
+      "K_a p |- K_b p",
+      "[a]p |- [b]p",
+      "K_a p, K_b p |- C p",
+      "K_a p, K_b p, E E p |- C p",
+      "C p, K_a q, K_b q |- C q",
+    ];
+    for thm in cases.iter() {
+      let thm = ModalParser::parse_theorem(thm).unwrap();
+      let t = Tableau::for_theorem(&thm, FrameConditions::K);
+      let countermodel = t.countermodel().expect("invalid inferences have countermodels");
+      assert!(countermodel.is_countermodel(thm.assumptions(), thm.conclusion()), "{}:\n{}", thm, countermodel);
+      for (x, _) in countermodel.model.indexed.iter() {
+        let conditions = FrameConditions::K.for_index(*x);
+        assert!(countermodel.model.indexed_satisfies(*x, conditions), "{}:\n{}", thm, countermodel);
+      }
+    }
+  }
+
+  #[test]
+  fn test_common_knowledge_loops() {
+    // `¬C p` is handed on from world 0 to 1 and from 1 to 2, which is blocked by 1.  Nothing on that branch contradicts
+    // anything else, but `p` is never false on it, so it is closed by the loop check.
+    let thm = ModalParser::parse_theorem("K_a p |- C p").unwrap();
+    let t = Tableau::for_theorem(&thm, FrameConditions::K);
+    assert!(t.is_complete() && t.is_closed(), "{}", t);
+    let parse = |s: &str| ModalParser::parse_expression(s).unwrap();
+    let looped = t
+      .tree()
+      .leaves()
+      .into_iter()
+      .map(|leaf| t.tree().branch_contents(leaf))
+      .find(|lines| lines.contains(&&Line::Formula(parse("~C p"), 2)))
+      .expect("a branch hands ~C p on to world 2");
+    assert!(looped.iter().all(|line| !matches!(line, Line::Formula(e, _) if *e == parse("~p"))), "{}", t);
+  }
+
+  #[test]
+  fn test_possible_creates_world() {
+    let t = Tableau::for_theorem(&ModalParser::parse_theorem("|- ~<>p").unwrap(), FrameConditions::K);
+    let branches = t.open_branches();
+    assert_eq!(branches.len(), 1);
+    assert!(branches[0].contains(&&Line::Accessible(0, 1)));
+  }
+}
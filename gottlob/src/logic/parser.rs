@@ -0,0 +1,227 @@
+//! The parser shared by every formula language.  It accepts any operator we know about and then asks the `Formula`
+//! being built whether that operator belongs to its language.
+
+use lazy_static::*;
+use pest::iterators::{Pair, Pairs};
+pub use pest::prec_climber::Assoc;
+use pest::prec_climber::{Operator as ClimberOperator, PrecClimber};
+use pest::Parser;
+use pest_derive::*;
+use super::formula::{Agent, Formula, Index, Operator, Shape, Theorem, Variable};
+use super::{ParseError, RuleDescription};
+
+/// The binary connectives from the loosest to the tightest binding, with how a run of each is grouped.  The printer
+/// reads the same table, so that what it leaves out is exactly what the parser puts back.  Exclusive or binds like the
+/// biconditional it negates, and NOR and NAND just tighter than the connectives they negate.
+pub const BINARY_OPERATORS: [(Operator, Assoc); 7] = [
+  (Operator::Biconditional, Assoc::Left),
+  (Operator::Xor, Assoc::Left),
+  (Operator::Conditional, Assoc::Right),
+  (Operator::Or, Assoc::Left),
+  (Operator::Nor, Assoc::Left),
+  (Operator::And, Assoc::Left),
+  (Operator::Nand, Assoc::Left),
+];
+
+/// Where a binary connective sits in `BINARY_OPERATORS`, higher binding tighter.
+pub fn precedence(op: Operator) -> Option<(usize, Assoc)> {
+  BINARY_OPERATORS
+    .iter()
+    .position(|(o, _)| *o == op)
+    .map(|i| (i, BINARY_OPERATORS[i].1))
+}
+
+fn binary_rule(op: Operator) -> Rule {
+  match op {
+    Operator::And => Rule::and,
+    Operator::Or => Rule::or,
+    Operator::Conditional => Rule::conditional,
+    Operator::Biconditional => Rule::biconditional,
+    Operator::Nand => Rule::nand,
+    Operator::Nor => Rule::nor,
+    Operator::Xor => Rule::xor,
+    _ => unreachable!("{:?} is not binary", op),
+  }
+}
+
+lazy_static! {
+  static ref PREC_CLIMBER: PrecClimber<Rule> = {
+    PrecClimber::new(
+      BINARY_OPERATORS
+        .iter()
+        .map(|(op, assoc)| ClimberOperator::new(binary_rule(*op), *assoc))
+        .collect(),
+    )
+  };
+}
+
+// TODO: consider `grammar_inline`
+#[derive(Parser)]
+#[grammar = "logic/grammar.pest"]
+pub struct FormulaParser;
+
+impl FormulaParser {
+  pub fn parse_theorem<F: Formula>(s: &str) -> Result<Theorem<F>, ParseError> {
+    let thm = Self::parse(Rule::whole_theorem, s)
+      .map_err(|e| ParseError::from_pest(s, e))?
+      .next()
+      .unwrap();
+    let mut inner = thm.into_inner().collect::<Vec<_>>();
+    let conclusion = Self::handle_expression_parse_tree(s, inner.pop().expect("always has a conclusion"))?;
+    let proves = Self::handle_theorem_op_parse_tree(inner.pop().expect("always has an op"));
+    let assumptions = inner
+      .into_iter()
+      .map(|expr| Self::handle_expression_parse_tree(s, expr))
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(Theorem::new(assumptions, proves, conclusion))
+  }
+
+  pub fn parse_expression<F: Formula>(s: &str) -> Result<F, ParseError> {
+    let expr = Self::parse(Rule::whole_expr, s)
+      .map_err(|e| ParseError::from_pest(s, e))?
+      .next()
+      .unwrap();
+    Self::handle_expression_parse_tree(s, expr)
+  }
+
+  /// You _must_ give this the parse tree for a theorem op.
+  fn handle_theorem_op_parse_tree(expr_tree: Pair<Rule>) -> bool {
+    match expr_tree.as_rule() {
+      Rule::proves => true,
+      Rule::does_not_prove => false,
+      _ => panic!("parse tree must be for a theorem op"),
+    }
+  }
+
+  /// You _must_ give this the parse tree for an expression.
+  fn handle_expression_parse_tree<F: Formula>(input: &str, expr_tree: Pair<Rule>) -> Result<F, ParseError> {
+    fn build<F: Formula>(input: &str, pair: &Pair<Rule>, shape: Shape<F>) -> Result<F, ParseError> {
+      F::try_from_shape(shape).map_err(|operator| {
+        let span = pair.as_span();
+        ParseError::Unsupported {
+          input: input.to_owned(),
+          span: span.start()..span.end(),
+          operator,
+          language: F::language(),
+        }
+      })
+    }
+
+    fn primary<F: Formula>(input: &str, pair: Pair<Rule>) -> Result<F, ParseError> {
+      match pair.as_rule() {
+        Rule::expr => with_prec(input, pair.into_inner()),
+        Rule::term => primary(input, pair.into_inner().next().unwrap()),
+        Rule::negated
+        | Rule::necessary
+        | Rule::possible
+        | Rule::future_always
+        | Rule::future_sometime
+        | Rule::past_always
+        | Rule::past_sometime
+        | Rule::indexed_necessary
+        | Rule::indexed_possible
+        | Rule::knows
+        | Rule::believes
+        | Rule::everybody_knows
+        | Rule::common_knowledge => {
+          let op = match pair.as_rule() {
+            Rule::negated => Operator::Not,
+            Rule::necessary => Operator::Necessary,
+            Rule::possible => Operator::Possible,
+            Rule::future_always => Operator::FutureAlways,
+            Rule::future_sometime => Operator::FutureSometime,
+            Rule::past_always => Operator::PastAlways,
+            Rule::past_sometime => Operator::PastSometime,
+            Rule::indexed_possible => Operator::IndexedPossible(index(pair.clone().into_inner().next().unwrap())),
+            Rule::everybody_knows => Operator::EverybodyKnows,
+            Rule::common_knowledge => Operator::CommonKnowledge,
+            _ => Operator::IndexedNecessary(index(pair.clone().into_inner().next().unwrap())),
+          };
+          let operand = primary(input, pair.clone().into_inner().last().unwrap())?;
+          build(input, &pair, Shape::Unary(op, operand))
+        }
+        Rule::literal => Ok(F::from_shape(Shape::Variable(Variable::new(pair.as_str())))),
+        Rule::top => Ok(F::from_shape(Shape::Constant(true))),
+        Rule::bottom => Ok(F::from_shape(Shape::Constant(false))),
+        Rule::grouped => primary(input, pair.into_inner().next().unwrap()),
+        _ => unreachable!(),
+      }
+    }
+
+    /// The index of an indexed operator, from its `knowledge`, `belief` or `agent` pair.
+    fn index(pair: Pair<Rule>) -> Index {
+      let rule = pair.as_rule();
+      let agent = Agent::new(pair.clone().into_inner().next().unwrap_or(pair).as_str());
+      match rule {
+        Rule::knowledge => Index::Knowledge(agent),
+        Rule::belief => Index::Belief(agent),
+        _ => Index::Named(agent),
+      }
+    }
+
+    fn with_prec<F: Formula>(input: &str, pairs: Pairs<Rule>) -> Result<F, ParseError> {
+      PREC_CLIMBER.climb(
+        pairs,
+        |pair: Pair<Rule>| primary(input, pair),
+        |lhs: Result<F, ParseError>, pair: Pair<Rule>, rhs: Result<F, ParseError>| {
+          let op = match pair.as_rule() {
+            Rule::and => Operator::And,
+            Rule::or => Operator::Or,
+            Rule::conditional => Operator::Conditional,
+            Rule::biconditional => Operator::Biconditional,
+            Rule::nand => Operator::Nand,
+            Rule::nor => Operator::Nor,
+            Rule::xor => Operator::Xor,
+            _ => unreachable!(),
+          };
+          build(input, &pair, Shape::Binary(op, lhs?, rhs?))
+        },
+      )
+    }
+
+    with_prec(input, expr_tree.into_inner())
+  }
+}
+
+impl RuleDescription for Rule {
+  fn description(&self) -> &'static str {
+    match self {
+      Self::expr | Self::whole_expr | Self::term => "a sentence",
+      Self::literal => "a sentence letter",
+      Self::top => "'T'",
+      Self::bottom => "'F'",
+      Self::negated => "a negation",
+      Self::not => "'~'",
+      Self::necessary => "'[]'",
+      Self::possible => "'<>'",
+      Self::future_always => "'G'",
+      Self::future_sometime => "'F'",
+      Self::past_always => "'H'",
+      Self::past_sometime => "'P'",
+      Self::agent | Self::index => "an agent",
+      Self::knowledge | Self::knows => "'K_'",
+      Self::belief | Self::believes => "'B_'",
+      Self::indexed_necessary => "'['",
+      Self::indexed_possible => "'<'",
+      Self::everybody_knows => "'E'",
+      Self::common_knowledge => "'C'",
+      Self::grouped => "an opening parenthesis",
+      Self::closing_paren => "a closing parenthesis",
+      Self::binary_op => "a connective",
+      Self::identifier_char => "a letter, digit or underscore",
+      Self::keyword => "a connective",
+      Self::and => "'^'",
+      Self::or => "'v'",
+      Self::conditional => "'->'",
+      Self::biconditional => "'<->'",
+      Self::nand => "'nand'",
+      Self::nor => "'nor'",
+      Self::xor => "'xor'",
+      Self::theorem | Self::whole_theorem => "a theorem",
+      Self::proves => "'|-'",
+      Self::does_not_prove => "'|/-'",
+      Self::EOI => "the end of the input",
+      Self::WHITESPACE => "a space",
+    }
+  }
+}
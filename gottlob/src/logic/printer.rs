@@ -0,0 +1,376 @@
+//! Writing formulas out again.  Infix styles only use the parentheses the parser needs to get the same formula back,
+//! working from the precedence table in `parser`, so that printing and then parsing gives back an equal formula.
+
+use super::formula::{Formula, Index, Operator, Shape, Variable};
+use super::parser::{precedence, Assoc};
+use std::fmt::Write;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub enum Style {
+  /// `¬p ∧ q → ◻r`, as `Display` writes it.
+  Unicode,
+  /// `~p ^ q -> []r`, what you would type in.
+  Ascii,
+  /// `-> ^ ~ p q [] r`, the prefix notation `ClassicalRpParser` reads.
+  Polish,
+  /// `p ~ q ^ r [] ->`, the same in postfix.
+  ReversePolish,
+  /// `(¬p ∧ q) ⊃ □r`, as in Priest's book: every binary connective gets parentheses except the outermost.
+  Priest,
+  /// `\neg p \wedge q \rightarrow \Box r`, for LaTeX's math mode.
+  Latex,
+}
+
+impl Style {
+  pub const ALL: [Self; 6] = [
+    Self::Unicode,
+    Self::Ascii,
+    Self::Polish,
+    Self::ReversePolish,
+    Self::Priest,
+    Self::Latex,
+  ];
+
+  pub fn symbol(&self, op: Operator) -> String {
+    let symbol = match self {
+      Self::Unicode => return op.symbol(),
+      Self::Ascii | Self::Polish | Self::ReversePolish => match op {
+        Operator::Not => "~",
+        Operator::And => "^",
+        Operator::Or => "v",
+        Operator::Conditional => "->",
+        Operator::Biconditional => "<->",
+        Operator::Nand => "nand",
+        Operator::Nor => "nor",
+        Operator::Xor => "xor",
+        Operator::Necessary => "[]",
+        Operator::Possible => "<>",
+        Operator::IndexedPossible(i) => return format!("<{}>", i),
+        op => return op.symbol(),
+      },
+      Self::Priest => match op {
+        Operator::Conditional => "⊃",
+        Operator::Biconditional => "≡",
+        Operator::Necessary => "□",
+        op => return op.symbol(),
+      },
+      Self::Latex => match op {
+        Operator::Not => "\\neg ",
+        Operator::And => "\\wedge",
+        Operator::Or => "\\vee",
+        Operator::Conditional => "\\rightarrow",
+        Operator::Biconditional => "\\leftrightarrow",
+        Operator::Nand => "\\uparrow",
+        Operator::Nor => "\\downarrow",
+        Operator::Xor => "\\oplus",
+        Operator::Necessary => "\\Box ",
+        Operator::Possible => "\\Diamond ",
+        Operator::FutureAlways => "G ",
+        Operator::FutureSometime => "F ",
+        Operator::PastAlways => "H ",
+        Operator::PastSometime => "P ",
+        Operator::IndexedNecessary(Index::Named(a)) => return format!("[{}] ", a),
+        Operator::IndexedNecessary(i) => return format!("{} ", latex_index(i)),
+        Operator::IndexedPossible(i) => return format!("\\langle {} \\rangle ", latex_index(i)),
+        Operator::EverybodyKnows => "E ",
+        Operator::CommonKnowledge => "C ",
+      },
+    };
+    symbol.to_owned()
+  }
+
+  pub fn constant(&self, value: bool) -> &'static str {
+    match (self, value) {
+      (Self::Unicode, true) | (Self::Priest, true) => "⊤",
+      (Self::Unicode, false) | (Self::Priest, false) => "⊥",
+      (Self::Latex, true) => "\\top",
+      (Self::Latex, false) => "\\bot",
+      (_, true) => "T",
+      (_, false) => "F",
+    }
+  }
+
+  /// LaTeX would run the letters of `rain` together and read `q_12` as `q₁2`, so there it gets `\mathit{rain}` and
+  /// `q_{12}`.
+  pub fn variable(&self, v: Variable) -> String {
+    let name = v.name();
+    if *self != Self::Latex {
+      return name;
+    }
+    let (base, subscript) = match name.find('_') {
+      Some(i) => (&name[..i], Some(&name[i + 1..])),
+      None => (name.as_str(), None),
+    };
+    let mut s = if base.chars().count() > 1 {
+      format!("\\mathit{{{}}}", base)
+    } else {
+      base.to_owned()
+    };
+    if let Some(subscript) = subscript {
+      s.push_str(&format!("_{{{}}}", subscript.replace('_', "\\_")));
+    }
+    s
+  }
+}
+
+fn latex_index(i: Index) -> String {
+  match i {
+    Index::Named(a) => a.to_string(),
+    Index::Knowledge(a) => format!("K_{{{}}}", a),
+    Index::Belief(a) => format!("B_{{{}}}", a),
+  }
+}
+
+pub fn print<F: Formula>(formula: &F, style: Style) -> String {
+  let mut s = String::new();
+  write(formula, style, &mut s).expect("writing to a string can't fail");
+  s
+}
+
+pub fn write<F: Formula, W: Write>(formula: &F, style: Style, w: &mut W) -> std::fmt::Result {
+  match style {
+    Style::Polish => write_prefix(formula, style, w),
+    Style::ReversePolish => write_postfix(formula, style, w),
+    Style::Unicode | Style::Ascii | Style::Priest | Style::Latex => write_infix(formula, style, w),
+  }
+}
+
+fn write_infix<F: Formula, W: Write>(formula: &F, style: Style, w: &mut W) -> std::fmt::Result {
+  match formula.shape() {
+    Shape::Variable(v) => write!(w, "{}", style.variable(v)),
+    Shape::Constant(c) => write!(w, "{}", style.constant(c)),
+    Shape::Unary(op, a) => {
+      write!(w, "{}", style.symbol(op))?;
+      // `K_a` and `B_a` would otherwise run into a sentence letter after them.
+      if style != Style::Latex && matches!(op, Operator::IndexedNecessary(Index::Knowledge(_) | Index::Belief(_))) {
+        write!(w, " ")?;
+      }
+      write_operand(a, style, needs_parens(a, style, None), w)
+    }
+    Shape::Binary(op, a, b) => {
+      write_operand(a, style, needs_parens(a, style, Some((op, Side::Left))), w)?;
+      write!(w, " {} ", style.symbol(op))?;
+      write_operand(b, style, needs_parens(b, style, Some((op, Side::Right))), w)
+    }
+  }
+}
+
+fn write_operand<F: Formula, W: Write>(formula: &F, style: Style, parens: bool, w: &mut W) -> std::fmt::Result {
+  if parens {
+    write!(w, "(")?;
+    write_infix(formula, style, w)?;
+    write!(w, ")")
+  } else {
+    write_infix(formula, style, w)
+  }
+}
+
+#[derive(Eq, PartialEq)]
+enum Side {
+  Left,
+  Right,
+}
+
+/// Whether `operand` needs parentheses under `parent`, which is a binary connective and the side `operand` is on, or
+/// `None` for a unary one.  Unary connectives bind tighter than any binary one.
+fn needs_parens<F: Formula>(operand: &F, style: Style, parent: Option<(Operator, Side)>) -> bool {
+  let inner = match operand.shape() {
+    Shape::Binary(op, _, _) => op,
+    _ => return false,
+  };
+  let (outer, side) = match parent {
+    Some(parent) if style != Style::Priest => parent,
+    _ => return true,
+  };
+  let (inner_precedence, _) = precedence(inner).expect("binary connectives have a precedence");
+  let (outer_precedence, assoc) = precedence(outer).expect("binary connectives have a precedence");
+  match inner_precedence.cmp(&outer_precedence) {
+    std::cmp::Ordering::Less => true,
+    std::cmp::Ordering::Greater => false,
+    std::cmp::Ordering::Equal => match assoc {
+      Assoc::Left => side == Side::Right,
+      Assoc::Right => side == Side::Left,
+    },
+  }
+}
+
+fn write_prefix<F: Formula, W: Write>(formula: &F, style: Style, w: &mut W) -> std::fmt::Result {
+  match formula.shape() {
+    Shape::Variable(v) => write!(w, "{}", style.variable(v)),
+    Shape::Constant(c) => write!(w, "{}", style.constant(c)),
+    Shape::Unary(op, a) => {
+      write!(w, "{} ", style.symbol(op))?;
+      write_prefix(a, style, w)
+    }
+    Shape::Binary(op, a, b) => {
+      write!(w, "{} ", style.symbol(op))?;
+      write_prefix(a, style, w)?;
+      write!(w, " ")?;
+      write_prefix(b, style, w)
+    }
+  }
+}
+
+fn write_postfix<F: Formula, W: Write>(formula: &F, style: Style, w: &mut W) -> std::fmt::Result {
+  match formula.shape() {
+    Shape::Variable(v) => write!(w, "{}", style.variable(v)),
+    Shape::Constant(c) => write!(w, "{}", style.constant(c)),
+    Shape::Unary(op, a) => {
+      write_postfix(a, style, w)?;
+      write!(w, " {}", style.symbol(op))
+    }
+    Shape::Binary(op, a, b) => {
+      write_postfix(a, style, w)?;
+      write!(w, " ")?;
+      write_postfix(b, style, w)?;
+      write!(w, " {}", style.symbol(op))
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+  use crate::logic::classical::parser_reverse_polish::ClassicalRpParser;
+  use crate::logic::formula::Variable;
+  use crate::logic::modal::parser::ModalParser;
+  use crate::logic::{classical, modal};
+
+  const SENTENCES: [&str; 15] = [
+    "p",
+    "~~p",
+    "p ^ q ^ r",
+    "p ^ (q ^ r)",
+    "p -> q -> r",
+    "(p -> q) -> r",
+    "p <-> q <-> r",
+    "p <-> (q <-> r)",
+    "~(p v q) ^ r -> s <-> t",
+    "p ^ (q v r) ^ ~(s -> T)",
+    "(p <-> q) v F",
+    "rain_1 -> (p_2 v ~q ^ r)",
+    "p nand q nand r",
+    "p nand (q nand r) nor p ^ q",
+    "(p xor q -> r) xor s <-> t",
+  ];
+
+  const MODAL_SENTENCES: [&str; 6] = [
+    "[](p -> q) -> []p -> []q",
+    "<>~[]p",
+    "~[](p ^ <>(q v r))",
+    "[]p ^ <>T",
+    "G(p -> Hq) v P~p",
+    "HGrain ^ ~PG[]p",
+  ];
+
+  /// Indexed operators are left out of the Polish round trips, since `parse_polish` only knows `ALL_OPERATORS`.
+  const EPISTEMIC_SENTENCES: [&str; 3] = ["K_a p -> p", "~B_bob ~<K_a>(p v [1]q)", "C(p ^ E ~<a>q) -> E p"];
+
+  /// Reads a formula back from Polish or reverse Polish notation with a stack.  Polish notation is reverse Polish read
+  /// backwards, except that the operands come off the stack the other way round.
+  fn parse_polish<F: Formula>(s: &str, style: Style) -> F {
+    let mut tokens = s.split(' ').collect::<Vec<_>>();
+    if style == Style::Polish {
+      tokens.reverse();
+    }
+    let mut stack: Vec<F> = Vec::new();
+    for token in tokens {
+      let op = ALL_OPERATORS.iter().find(|op| style.symbol(**op) == token);
+      let shape = match (token, op) {
+        ("T", _) => Shape::Constant(true),
+        ("F", _) => Shape::Constant(false),
+        (_, Some(op)) if op.is_unary() => Shape::Unary(*op, stack.pop().unwrap()),
+        (_, Some(op)) => {
+          let (a, b) = (stack.pop().unwrap(), stack.pop().unwrap());
+          match style {
+            Style::Polish => Shape::Binary(*op, a, b),
+            _ => Shape::Binary(*op, b, a),
+          }
+        }
+        (_, None) => Shape::Variable(Variable::new(token)),
+      };
+      stack.push(F::from_shape(shape));
+    }
+    assert_eq!(stack.len(), 1);
+    stack.pop().unwrap()
+  }
+
+  const ALL_OPERATORS: [Operator; 14] = [
+    Operator::Not,
+    Operator::And,
+    Operator::Or,
+    Operator::Conditional,
+    Operator::Biconditional,
+    Operator::Nand,
+    Operator::Nor,
+    Operator::Xor,
+    Operator::Necessary,
+    Operator::Possible,
+    Operator::FutureAlways,
+    Operator::FutureSometime,
+    Operator::PastAlways,
+    Operator::PastSometime,
+  ];
+
+  #[test]
+  fn test_minimal_parentheses() {
+    let classical = |s: &str, style: Style| print(&ClassicalParser::parse_expression(s).unwrap(), style);
+    assert_eq!(classical("((p ^ q)) ^ r", Style::Unicode), "p ∧ q ∧ r");
+    assert_eq!(classical("p ^ (q ^ r)", Style::Unicode), "p ∧ (q ∧ r)");
+    assert_eq!(classical("(p -> q) -> (r -> s)", Style::Unicode), "(p → q) → r → s");
+    assert_eq!(classical("~(p v q) ^ r -> s <-> t", Style::Unicode), "¬(p ∨ q) ∧ r → s ↔ t");
+    assert_eq!(classical("~(p v q) ^ r -> s <-> t", Style::Ascii), "~(p v q) ^ r -> s <-> t");
+    assert_eq!(classical("~(p v q) ^ r -> s <-> t", Style::Priest), "((¬(p ∨ q) ∧ r) ⊃ s) ≡ t");
+    assert_eq!(classical("~(p v q) ^ r -> s <-> t", Style::Polish), "<-> -> ^ ~ v p q r s t");
+    assert_eq!(classical("~(p v q) ^ r -> s <-> t", Style::ReversePolish), "p q v ~ r ^ s -> t <->");
+    assert_eq!(classical("T -> ~F", Style::Ascii), "T -> ~F");
+    assert_eq!(classical("(p nand q) ^ (p nor q) v (p xor q)", Style::Unicode), "p ↑ q ∧ (p ↓ q) ∨ (p ⊕ q)");
+    assert_eq!(classical("p ↑ (q ⊕ r)", Style::Latex), r"p \uparrow (q \oplus r)");
+    let m = ModalParser::parse_expression("[](p -> q) -> []p -> []q").unwrap();
+    assert_eq!(print(&m, Style::Unicode), "◻(p → q) → ◻p → ◻q");
+    assert_eq!(print(&m, Style::Priest), "□(p ⊃ q) ⊃ (□p ⊃ □q)");
+    assert_eq!(print(&m, Style::Latex), r"\Box (p \rightarrow q) \rightarrow \Box p \rightarrow \Box q");
+    let e = ModalParser::parse_expression("~rain v q_12 ^ <>F").unwrap();
+    assert_eq!(print(&e, Style::Latex), r"\neg \mathit{rain} \vee q_{12} \wedge \Diamond \bot");
+    let t = ModalParser::parse_expression("F p -> ~F ^ GHF").unwrap();
+    assert_eq!(print(&t, Style::Unicode), "Fp → ¬⊥ ∧ GH⊥");
+    assert_eq!(print(&t, Style::Ascii), "Fp -> ~F ^ GHF");
+    assert_eq!(print(&t, Style::Latex), r"F p \rightarrow \neg \bot \wedge G H \bot");
+    let k = ModalParser::parse_expression("K_a ~<B_b>p ^ [c]E C q").unwrap();
+    assert_eq!(print(&k, Style::Unicode), "K_a ¬⟨B_b⟩p ∧ [c]ECq");
+    assert_eq!(print(&k, Style::Ascii), "K_a ~<B_b>p ^ [c]ECq");
+    assert_eq!(print(&k, Style::Polish), "^ K_a ~ <B_b> p [c] E C q");
+    assert_eq!(print(&k, Style::Latex), r"K_{a} \neg \langle B_{b} \rangle p \wedge [c] E C q");
+  }
+
+  #[test]
+  fn test_round_trips() {
+    for s in SENTENCES.iter() {
+      let e: classical::Expression = ClassicalParser::parse_expression(s).unwrap();
+      for style in [Style::Unicode, Style::Ascii, Style::Priest].iter() {
+        let printed = print(&e, *style);
+        assert_eq!(ClassicalParser::parse_expression(&printed).unwrap(), e, "{} in {:?}", printed, style);
+      }
+      assert_eq!(ClassicalRpParser::parse_expression(&print(&e, Style::Polish)).unwrap(), e);
+      assert_eq!(parse_polish::<classical::Expression>(&print(&e, Style::ReversePolish), Style::ReversePolish), e);
+    }
+    for s in SENTENCES.iter().chain(MODAL_SENTENCES.iter()) {
+      let e: modal::Expression = ModalParser::parse_expression(s).unwrap();
+      for style in [Style::Unicode, Style::Ascii, Style::Priest].iter() {
+        let printed = print(&e, *style);
+        assert_eq!(ModalParser::parse_expression(&printed).unwrap(), e, "{} in {:?}", printed, style);
+      }
+      for style in [Style::Polish, Style::ReversePolish].iter() {
+        assert_eq!(parse_polish::<modal::Expression>(&print(&e, *style), *style), e);
+      }
+    }
+    for s in EPISTEMIC_SENTENCES.iter() {
+      let e: modal::Expression = ModalParser::parse_expression(s).unwrap();
+      for style in [Style::Unicode, Style::Ascii, Style::Priest].iter() {
+        let printed = print(&e, *style);
+        assert_eq!(ModalParser::parse_expression(&printed).unwrap(), e, "{} in {:?}", printed, style);
+      }
+    }
+  }
+}
@@ -0,0 +1,333 @@
+//! Simplifying formulas one rewrite at a time, keeping the rewrites so they can be shown to someone learning why the
+//! result is equivalent to where we started.
+//!
+//! Every rule makes the formula strictly smaller, so simplifying always stops.  Rewrites are made innermost first and
+//! left to right.  The modal rules hold in every normal modal logic, and for the tense operators in every tense logic,
+//! so a simplification is an equivalence in all of them, and of course classically.  The indexed operators are boxes
+//! and diamonds of their own, so the same rules hold of them too.
+
+use super::formula::{Formula, Operator, Shape};
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub enum RewriteRule {
+  DoubleNegation,
+  Idempotence,
+  Absorption,
+  Complement,
+  Reflexivity,
+  ConstantPropagation,
+  ModalDuality,
+  Necessitation,
+}
+
+impl RewriteRule {
+  pub const ALL: [Self; 8] = [
+    Self::DoubleNegation,
+    Self::Idempotence,
+    Self::Absorption,
+    Self::Complement,
+    Self::Reflexivity,
+    Self::ConstantPropagation,
+    Self::ModalDuality,
+    Self::Necessitation,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::DoubleNegation => "double negation",
+      Self::Idempotence => "idempotence",
+      Self::Absorption => "absorption",
+      Self::Complement => "complement",
+      Self::Reflexivity => "reflexivity",
+      Self::ConstantPropagation => "constant propagation",
+      Self::ModalDuality => "modal duality",
+      Self::Necessitation => "necessitation",
+    }
+  }
+
+  /// The rewrites the rule makes.  Conjunction, disjunction and the biconditional are taken either way round.
+  pub fn schemas(&self) -> &'static [&'static str] {
+    match self {
+      Self::DoubleNegation => &["¬¬A ⇒ A"],
+      Self::Idempotence => &["A ∧ A ⇒ A", "A ∨ A ⇒ A"],
+      Self::Absorption => &["A ∧ (A ∨ B) ⇒ A", "A ∨ A ∧ B ⇒ A"],
+      Self::Complement => &["A ∧ ¬A ⇒ ⊥", "A ∨ ¬A ⇒ ⊤"],
+      Self::Reflexivity => &["A → A ⇒ ⊤", "A ↔ A ⇒ ⊤"],
+      Self::ConstantPropagation => &[
+        "¬⊤ ⇒ ⊥",
+        "¬⊥ ⇒ ⊤",
+        "A ∧ ⊤ ⇒ A",
+        "A ∧ ⊥ ⇒ ⊥",
+        "A ∨ ⊤ ⇒ ⊤",
+        "A ∨ ⊥ ⇒ A",
+        "⊤ → A ⇒ A",
+        "⊥ → A ⇒ ⊤",
+        "A → ⊤ ⇒ ⊤",
+        "A → ⊥ ⇒ ¬A",
+        "A ↔ ⊤ ⇒ A",
+        "A ↔ ⊥ ⇒ ¬A",
+      ],
+      Self::ModalDuality => &[
+        "¬◻¬A ⇒ ◇A",
+        "¬◇¬A ⇒ ◻A",
+        "¬G¬A ⇒ FA",
+        "¬F¬A ⇒ GA",
+        "¬H¬A ⇒ PA",
+        "¬P¬A ⇒ HA",
+        "¬[a]¬A ⇒ ⟨a⟩A",
+        "¬⟨a⟩¬A ⇒ [a]A",
+      ],
+      Self::Necessitation => &[
+        "◻⊤ ⇒ ⊤",
+        "◇⊥ ⇒ ⊥",
+        "G⊤ ⇒ ⊤",
+        "F⊥ ⇒ ⊥",
+        "H⊤ ⇒ ⊤",
+        "P⊥ ⇒ ⊥",
+        "[a]⊤ ⇒ ⊤",
+        "⟨a⟩⊥ ⇒ ⊥",
+        "E⊤ ⇒ ⊤",
+        "C⊤ ⇒ ⊤",
+      ],
+    }
+  }
+}
+
+impl std::fmt::Display for RewriteRule {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+/// One rewrite: the subformula `from` was replaced by `to` using `rule`, giving `result`.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Step<F> {
+  pub rule: RewriteRule,
+  pub from: F,
+  pub to: F,
+  pub result: F,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Simplification<F> {
+  pub original: F,
+  pub result: F,
+  pub steps: Vec<Step<F>>,
+}
+
+/// Shows the simplification as a chain of equivalences, each line saying which rewrite got there.
+impl<F: std::fmt::Display> std::fmt::Display for Simplification<F> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.original)?;
+    for step in &self.steps {
+      write!(f, "\n≡ {}    by {}: {} ⇒ {}", step.result, step.rule, step.from, step.to)?;
+    }
+    Ok(())
+  }
+}
+
+pub fn simplify<F: Formula + Clone + Eq>(f: &F) -> Simplification<F> {
+  let mut steps = Vec::new();
+  let mut current = f.clone();
+  while let Some(step) = rewrite_somewhere(&current) {
+    current = step.result.clone();
+    steps.push(step);
+  }
+  Simplification {
+    original: f.clone(),
+    result: current,
+    steps,
+  }
+}
+
+/// The first rewrite to make inside `f`, innermost first.
+fn rewrite_somewhere<F: Formula + Clone + Eq>(f: &F) -> Option<Step<F>> {
+  let inner = match f.shape() {
+    Shape::Unary(op, a) => rewrite_somewhere(a).map(|s| (F::from_shape(Shape::Unary(op, s.result.clone())), s)),
+    Shape::Binary(op, a, b) => match rewrite_somewhere(a) {
+      Some(s) => Some((F::from_shape(Shape::Binary(op, s.result.clone(), b.clone())), s)),
+      None => rewrite_somewhere(b).map(|s| (F::from_shape(Shape::Binary(op, a.clone(), s.result.clone())), s)),
+    },
+    Shape::Variable(_) | Shape::Constant(_) => None,
+  };
+  if let Some((result, step)) = inner {
+    return Some(Step { result, ..step });
+  }
+  let (rule, to) = rewrite(f)?;
+  Some(Step {
+    rule,
+    from: f.clone(),
+    to: to.clone(),
+    result: to,
+  })
+}
+
+fn constant<F: Formula>(value: bool) -> F {
+  F::from_shape(Shape::Constant(value))
+}
+
+fn negation<F: Formula + Clone>(f: &F) -> F {
+  F::from_shape(Shape::Unary(Operator::Not, f.clone()))
+}
+
+fn constant_value<F: Formula>(f: &F) -> Option<bool> {
+  match f.shape() {
+    Shape::Constant(c) => Some(c),
+    _ => None,
+  }
+}
+
+/// Whether `a` is `¬b`.
+fn negates<F: Formula + Eq>(a: &F, b: &F) -> bool {
+  matches!(a.shape(), Shape::Unary(Operator::Not, c) if c == b)
+}
+
+/// Whether `f` is `a` joined to something else by `op`, in either order.
+fn has_operand<F: Formula + Eq>(f: &F, op: Operator, a: &F) -> bool {
+  matches!(f.shape(), Shape::Binary(o, x, y) if o == op && (x == a || y == a))
+}
+
+/// The rewrite to make at the top of `f`, if there is one.
+fn rewrite<F: Formula + Clone + Eq>(f: &F) -> Option<(RewriteRule, F)> {
+  use Operator::*;
+  match f.shape() {
+    Shape::Unary(Not, a) => match a.shape() {
+      Shape::Unary(Not, b) => Some((RewriteRule::DoubleNegation, b.clone())),
+      Shape::Constant(c) => Some((RewriteRule::ConstantPropagation, constant(!c))),
+      Shape::Unary(op, b) if op.dual().is_some() => match b.shape() {
+        Shape::Unary(Not, c) => {
+          let dual = op.dual().unwrap();
+          Some((RewriteRule::ModalDuality, F::from_shape(Shape::Unary(dual, c.clone()))))
+        }
+        _ => None,
+      },
+      _ => None,
+    },
+    // The boxes are true of `⊤` whatever worlds there are, and the diamonds false of `⊥`.
+    Shape::Unary(Necessary | FutureAlways | PastAlways | IndexedNecessary(_) | EverybodyKnows | CommonKnowledge, a)
+      if constant_value(a) == Some(true) =>
+    {
+      Some((RewriteRule::Necessitation, constant(true)))
+    }
+    Shape::Unary(Possible | FutureSometime | PastSometime | IndexedPossible(_), a)
+      if constant_value(a) == Some(false) =>
+    {
+      Some((RewriteRule::Necessitation, constant(false)))
+    }
+    Shape::Binary(op @ And, a, b) | Shape::Binary(op @ Or, a, b) => {
+      // The constant that leaves the other operand as it is: ⊤ for conjunction and ⊥ for disjunction.
+      let unit = op == And;
+      let dual = if unit { Or } else { And };
+      let (ca, cb) = (constant_value(a), constant_value(b));
+      if a == b {
+        Some((RewriteRule::Idempotence, a.clone()))
+      } else if ca == Some(!unit) || cb == Some(!unit) {
+        Some((RewriteRule::ConstantPropagation, constant(!unit)))
+      } else if ca == Some(unit) {
+        Some((RewriteRule::ConstantPropagation, b.clone()))
+      } else if cb == Some(unit) {
+        Some((RewriteRule::ConstantPropagation, a.clone()))
+      } else if negates(a, b) || negates(b, a) {
+        Some((RewriteRule::Complement, constant(!unit)))
+      } else if has_operand(b, dual, a) {
+        Some((RewriteRule::Absorption, a.clone()))
+      } else if has_operand(a, dual, b) {
+        Some((RewriteRule::Absorption, b.clone()))
+      } else {
+        None
+      }
+    }
+    Shape::Binary(Conditional, a, b) => match (constant_value(a), constant_value(b)) {
+      _ if a == b => Some((RewriteRule::Reflexivity, constant(true))),
+      (Some(true), _) => Some((RewriteRule::ConstantPropagation, b.clone())),
+      (Some(false), _) | (_, Some(true)) => Some((RewriteRule::ConstantPropagation, constant(true))),
+      (_, Some(false)) => Some((RewriteRule::ConstantPropagation, negation(a))),
+      _ => None,
+    },
+    Shape::Binary(Biconditional, a, b) => match (constant_value(a), constant_value(b)) {
+      _ if a == b => Some((RewriteRule::Reflexivity, constant(true))),
+      (Some(true), _) => Some((RewriteRule::ConstantPropagation, b.clone())),
+      (_, Some(true)) => Some((RewriteRule::ConstantPropagation, a.clone())),
+      (Some(false), _) => Some((RewriteRule::ConstantPropagation, negation(b))),
+      (_, Some(false)) => Some((RewriteRule::ConstantPropagation, negation(a))),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::logic::classical::parser::ClassicalParser;
+  use crate::logic::classical::semantics::are_equivalent;
+  use crate::logic::modal::parser::ModalParser;
+  use crate::logic::modal::tableau::Tableau;
+  use crate::logic::modal::{self, FrameConditions};
+
+  #[test]
+  fn test_classical() {
+    let e = ClassicalParser::parse_expression("~~(p ^ p) v F -> q ^ (q v r)").unwrap();
+    let s = e.simplify();
+    assert_eq!(s.result.to_string(), "p → q");
+    let rules = s.steps.iter().map(|s| s.rule).collect::<Vec<_>>();
+    assert_eq!(
+      rules,
+      vec![
+        RewriteRule::Idempotence,
+        RewriteRule::DoubleNegation,
+        RewriteRule::ConstantPropagation,
+        RewriteRule::Absorption
+      ]
+    );
+    assert_eq!(
+      s.to_string(),
+      "¬¬(p ∧ p) ∨ ⊥ → q ∧ (q ∨ r)
+≡ ¬¬p ∨ ⊥ → q ∧ (q ∨ r)    by idempotence: p ∧ p ⇒ p
+≡ p ∨ ⊥ → q ∧ (q ∨ r)    by double negation: ¬¬p ⇒ p
+≡ p → q ∧ (q ∨ r)    by constant propagation: p ∨ ⊥ ⇒ p
+≡ p → q    by absorption: q ∧ (q ∨ r) ⇒ q"
+    );
+
+    for (s, simplified) in [
+      ("p ^ ~p v q", "q"),
+      ("(p -> p) <-> q", "q"),
+      ("p <-> F", "¬p"),
+      ("(q v p) ^ p", "p"),
+      ("p -> q", "p → q"),
+      ("~T -> p", "⊤"),
+    ]
+    .iter()
+    {
+      let e = ClassicalParser::parse_expression(s).unwrap();
+      let result = e.simplify().result;
+      assert_eq!(result.to_string(), *simplified);
+      assert!(are_equivalent(&e, &result).holds, "{}", s);
+    }
+  }
+
+  #[test]
+  fn test_modal() {
+    let e = ModalParser::parse_expression("~[]~(p ^ T) v <>F").unwrap();
+    let s = e.simplify();
+    assert_eq!(s.result.to_string(), "◇p");
+    let cases = [
+      ("~<>~~~p", "◻p"),
+      ("[]T ^ q", "q"),
+      ("~[]~p v ~<>~p", "◇p ∨ ◻p"),
+      ("~G~p ^ ~P~q", "Fp ∧ Hq"),
+      ("GT ^ (q v P F)", "q"),
+      ("~K_a ~p -> [a]T", "⊤"),
+      ("~<B_b>~p v <a>F", "B_b p"),
+      ("C T ^ E ~~q", "Eq"),
+    ];
+    for (s, simplified) in cases.iter() {
+      let e = ModalParser::parse_expression(s).unwrap();
+      let result = e.simplify().result;
+      assert_eq!(result.to_string(), *simplified);
+      let equivalent = modal::Expression::Biconditional(Box::new(e), Box::new(result));
+      assert!(Tableau::new(&[], &equivalent, FrameConditions::K).is_closed(), "{}", s);
+    }
+    assert!(ModalParser::parse_expression("[]p").unwrap().simplify().steps.is_empty());
+  }
+}
@@ -0,0 +1,218 @@
+//! Shared machinery for analytic tableaux in the style of Priest's _An Introduction to Non-Classical Logic_.
+//!
+//! A tableau is a tree.  Every node carries some content (a formula, a world-labelled formula, an accessibility
+//! fact, ...) together with the rule that put it there.  A branch is the path from the root to a leaf, and a branch is
+//! closed once it contains a contradiction.  The individual logics decide what the content is and when a branch
+//! closes; this module just keeps track of the tree.
+
+use std::collections::BTreeMap;
+
+pub type NodeId = usize;
+
+/// The largest tableau we are willing to build.  Tableaux for the logics here are finite, but they can be exponentially
+/// large, so past this we give up rather than keep the user waiting.
+pub const MAX_NODES: usize = 5000;
+
+/// For rules that decompose a sentence once, in place.
+pub trait Branching {
+  /// Rules that split a branch are applied only once nothing else is left, which keeps trees small.
+  fn is_branching(&self) -> bool;
+}
+
+#[derive(Debug, Clone)]
+pub struct Node<T, R> {
+  pub content: T,
+  pub rule: R,
+  /// The node that was decomposed to produce this one, if any.
+  pub source: Option<NodeId>,
+  pub parent: Option<NodeId>,
+  pub children: Vec<NodeId>,
+  /// Only ever set on leaves.
+  pub closed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tree<T, R> {
+  nodes: Vec<Node<T, R>>,
+}
+
+impl<T, R> Default for Tree<T, R> {
+  fn default() -> Self {
+    Self { nodes: Vec::new() }
+  }
+}
+
+impl<T, R> Tree<T, R> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn root(&self) -> Option<NodeId> {
+    if self.nodes.is_empty() {
+      None
+    } else {
+      Some(0)
+    }
+  }
+
+  pub fn node(&self, id: NodeId) -> &Node<T, R> {
+    &self.nodes[id]
+  }
+
+  pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &Node<T, R>)> {
+    self.nodes.iter().enumerate()
+  }
+
+  pub fn len(&self) -> usize {
+    self.nodes.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.nodes.is_empty()
+  }
+
+  /// Adds a node below `parent`, or as the root if `parent` is `None`.
+  pub fn push(&mut self, parent: Option<NodeId>, content: T, rule: R, source: Option<NodeId>) -> NodeId {
+    assert!(
+      parent.is_some() || self.nodes.is_empty(),
+      "a tableau can only have one root"
+    );
+    let id = self.nodes.len();
+    self.nodes.push(Node {
+      content,
+      rule,
+      source,
+      parent,
+      children: Vec::new(),
+      closed: false,
+    });
+    if let Some(p) = parent {
+      self.nodes[p].children.push(id);
+    }
+    id
+  }
+
+  pub fn leaves(&self) -> Vec<NodeId> {
+    self.nodes().filter(|(_, n)| n.children.is_empty()).map(|(id, _)| id).collect()
+  }
+
+  pub fn open_leaves(&self) -> Vec<NodeId> {
+    self.leaves().into_iter().filter(|id| !self.nodes[*id].closed).collect()
+  }
+
+  /// The leaves of every branch that passes through `id`.
+  pub fn leaves_below(&self, id: NodeId) -> Vec<NodeId> {
+    let mut leaves = Vec::new();
+    let mut stack = vec![id];
+    while let Some(id) = stack.pop() {
+      let node = &self.nodes[id];
+      if node.children.is_empty() {
+        leaves.push(id);
+      } else {
+        stack.extend(node.children.iter().rev());
+      }
+    }
+    leaves
+  }
+
+  pub fn open_leaves_below(&self, id: NodeId) -> Vec<NodeId> {
+    self.leaves_below(id).into_iter().filter(|id| !self.nodes[*id].closed).collect()
+  }
+
+  /// The nodes of the branch ending at `leaf`, from the root down.
+  pub fn branch(&self, leaf: NodeId) -> Vec<NodeId> {
+    let mut branch = vec![leaf];
+    let mut current = leaf;
+    while let Some(parent) = self.nodes[current].parent {
+      branch.push(parent);
+      current = parent;
+    }
+    branch.reverse();
+    branch
+  }
+
+  pub fn branch_contents(&self, leaf: NodeId) -> Vec<&T> {
+    self.branch(leaf).into_iter().map(|id| &self.nodes[id].content).collect()
+  }
+
+  pub fn close(&mut self, leaf: NodeId) {
+    debug_assert!(self.nodes[leaf].children.is_empty(), "only leaves can be closed");
+    self.nodes[leaf].closed = true;
+  }
+
+  /// A tableau is closed when every one of its branches is.
+  pub fn is_closed(&self) -> bool {
+    !self.nodes.is_empty() && self.open_leaves().is_empty()
+  }
+}
+
+impl<T: Clone, R: Branching + Copy> Tree<T, R> {
+  /// Decomposes each node once, adding every branch `decompose` gives for it below each open leaf underneath it and
+  /// closing the new leaves that `contradictory` picks out.  Nodes go in order, those whose rules don't branch first.
+  /// Returns false if the tree grew past `MAX_NODES` before it was finished.
+  pub fn decompose_all(
+    &mut self,
+    decompose: impl Fn(&T) -> Option<(R, Vec<Vec<T>>)>,
+    contradictory: impl Fn(&Self, NodeId) -> bool,
+  ) -> bool {
+    // The nodes still to be decomposed, by whether their rule branches.
+    let mut pending = [BTreeMap::new(), BTreeMap::new()];
+    let mut queued = 0;
+    loop {
+      for id in queued..self.nodes.len() {
+        if let Some((rule, branches)) = decompose(&self.nodes[id].content) {
+          pending[rule.is_branching() as usize].insert(id, (rule, branches));
+        }
+      }
+      queued = self.nodes.len();
+      if self.nodes.len() > MAX_NODES {
+        return false;
+      }
+      let (id, (rule, branches)) = match pending[0].pop_first().or_else(|| pending[1].pop_first()) {
+        Some(next) => next,
+        None => return true,
+      };
+      for leaf in self.open_leaves_below(id) {
+        for branch in &branches {
+          let mut end = leaf;
+          for content in branch {
+            end = self.push(Some(end), content.clone(), rule, Some(id));
+          }
+          if contradictory(self, end) {
+            self.close(end);
+          }
+        }
+      }
+    }
+  }
+}
+
+impl<T: std::fmt::Display, R: std::fmt::Display> Tree<T, R> {
+  fn fmt_from(&self, f: &mut std::fmt::Formatter<'_>, id: NodeId, indent: usize) -> std::fmt::Result {
+    let node = &self.nodes[id];
+    let source = node.source.map(|s| format!(", {}", s + 1)).unwrap_or_default();
+    writeln!(f, "{:indent$}{}. {}  [{}{}]", "", id + 1, node.content, node.rule, source, indent = indent)?;
+    match node.children.as_slice() {
+      [] if node.closed => writeln!(f, "{:indent$}×", "", indent = indent),
+      [] => writeln!(f, "{:indent$}↑", "", indent = indent),
+      [child] => self.fmt_from(f, *child, indent),
+      children => {
+        for child in children {
+          self.fmt_from(f, *child, indent + 2)?;
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+/// Renders one node per line, numbered so that the `[rule, source]` annotations can refer back to earlier nodes.  Each
+/// split indents its branches; closed branches end in `×` and open ones in `↑`.
+impl<T: std::fmt::Display, R: std::fmt::Display> std::fmt::Display for Tree<T, R> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.root() {
+      Some(root) => self.fmt_from(f, root, 0),
+      None => Ok(()),
+    }
+  }
+}
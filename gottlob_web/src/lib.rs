@@ -7,6 +7,8 @@ use yew::prelude::*;
 use log::*;
 use gottlob::logic::Logic;
 use gottlob::logic::classical::ClassicalLogic;
+use gottlob::logic::intuitionistic::IntuitionisticLogic;
+use gottlob::logic::many_valued::TableauLogic;
 use gottlob::logic::modal::ModalLogic;
 
 #[derive(CustomDebug)]
@@ -41,12 +43,14 @@ impl Component for Repl {
       
 Enter sentences from modal logic to see if they are tautologies.
 
-  - sentence variables: lowercase letters such as 'p' and 'q' (but not 'v', since we use it elsewhere)
+  - sentence variables: words starting with a lowercase letter such as 'p', 'q_12' or 'rain' (but not 'v', since we use it for 'or')
+  - true and false: 'T' and 'F'
   - not: '~'
   - and: '^'
   - or: 'v'
   - conditional: '->'
   - biconditional: '<->'
+  - nand, nor and exclusive or: 'nand', 'nor' and 'xor'
   - possible: `<>`
   - necessary: `[]`
   
@@ -67,12 +71,19 @@ Try a sentence like '~(p ^ q) <-> ~p v ~q'!".to_owned()],
         if &command == "" { // nothing in the command line? give us a new line!
           return true;
         }
-        // TODO: better error display.
-        let classical_rs = ClassicalLogic.is_valid_theorem(&command).unwrap_or_else(|e| (format!("invalid classical parse {:?}", e), false));
-        let modal_rs = ModalLogic.is_valid_theorem(&command).unwrap_or_else(|e| (format!("invalid modal parse {:?}",e ), false));
+        let classical_rs = ClassicalLogic.is_valid_theorem(&command).unwrap_or_else(|e| (format!("invalid classical parse: {}", e), false));
         // TODO: ensure that this prompt will match the one the user uses.
         self.terminal.push(classical_rs.0);
-        self.terminal.push(modal_rs.0);
+        for logic in ModalLogic::SYSTEMS.iter().chain(ModalLogic::TENSE_SYSTEMS.iter()) {
+          let modal_rs = logic.is_valid_theorem(&command).unwrap_or_else(|e| (format!("invalid modal parse: {}", e), false));
+          self.terminal.push(format!("{}: {} ({})", logic.name(), modal_rs.1, modal_rs.0));
+        }
+        let intuitionistic_rs = IntuitionisticLogic.is_valid_theorem(&command).unwrap_or_else(|e| (format!("invalid parse: {}", e), false));
+        self.terminal.push(format!("{}: {} ({})", IntuitionisticLogic.name(), intuitionistic_rs.1, intuitionistic_rs.0));
+        for logic in TableauLogic::SYSTEMS.iter() {
+          let many_valued_rs = logic.is_valid_theorem(&command).unwrap_or_else(|e| (format!("invalid parse: {}", e), false));
+          self.terminal.push(format!("{}: {} ({})", logic.name(), many_valued_rs.1, many_valued_rs.0));
+        }
         self.history.push(command);
       },
       Msg::TerminalClicked => {